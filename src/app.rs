@@ -1,103 +1,261 @@
-use std::error::Error;
+use crate::{
+    checkerboard::CheckerboardSettings,
+    cli::ViewArgs,
+    color::U8Color,
+    gpu,
+    keymap::{Action, Keymap},
+    overlay::{Corner, TextOverlay},
+    pixel_grid::PixelGridSettings,
+    post_process::{PostProcess, PostProcessSettings},
+    renderer::{Compositing, Renderer, SourceAlpha},
+    scaling::{ScaleMode, Scaling},
+    srgb::{self, SrgbEncoder},
+    texture::{self, Texture},
+    view::View,
+    watch::FileWatcher,
+};
+use std::{error::Error, path::PathBuf};
 use winit::{
-    dpi::LogicalSize,
-    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{
+        ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode,
+        WindowEvent,
+    },
     event_loop::{ControlFlow, EventLoop},
     window::{Window, WindowBuilder},
 };
 
-struct U8Color {
-    r: u8,
-    g: u8,
-    b: u8,
-    a: u8,
-}
-
-impl U8Color {
-    const fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
-        Self { r, g, b, a }
-    }
-
-    fn to_wgpu(&self) -> wgpu::Color {
-        wgpu::Color {
-            r: (self.r as f64 / 255.0).powf(2.2),
-            g: (self.g as f64 / 255.0).powf(2.2),
-            b: (self.b as f64 / 255.0).powf(2.2),
-            a: self.a as f64 / 255.0,
-        }
-    }
+#[derive(Debug, thiserror::Error)]
+pub enum ViewerError {
+    #[error("Scale mode {0:?} needs textures larger than the device limit of {1} at any zoom")]
+    ScaleModeTooLarge(ScaleMode, u32),
 }
 
-const CORNFLOWER_BLUE: U8Color = U8Color::from_rgba(100, 149, 237, 255);
-
 struct Application {
     surface: wgpu::Surface,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
+    /// Format of the views of the surface, sRGB whenever the surface allows.
+    view_format: wgpu::TextureFormat,
+    /// Encodes frames into surfaces that need sRGB colors but have no sRGB
+    /// views.
+    srgb_encoder: Option<SrgbEncoder>,
+    /// Format that everything renders into: the view format, or that of the
+    /// sRGB encoder's texture.
+    render_format: wgpu::TextureFormat,
     size: winit::dpi::PhysicalSize<u32>,
+    renderer: Renderer,
+    /// Optional post-process stage the scaled image goes through.
+    post_process: Option<PostProcess>,
+    /// Post-processes the keymap cycles through, and which one is in use.
+    post_processes: [Option<PostProcessSettings>; 3],
+    post_process_index: usize,
+    scaling: Scaling,
+    /// Fixed scale from the command line, which resetting the view returns to.
+    initial_scale: Option<f64>,
+    /// Fixed scale to go back to when toggling off fitting the window.
+    fixed_scale: f64,
+    /// Zoom and pan from the mouse.
+    view: View,
+    /// Snap the window to whole multiples of the image when it is resized.
+    snap: bool,
+    /// Whether the window was asked to snap since it was last resized. That
+    /// resize isn't snapped again, so a window manager refusing the size
+    /// can't make it go back and forth.
+    snapping: bool,
+    keymap: Keymap,
+    background: U8Color,
+    /// Checkerboard the keymap shows and hides behind the image.
+    checkerboard: CheckerboardSettings,
+    show_checkerboard: bool,
+    source_alpha: SourceAlpha,
+    /// Pixel grid the keymap shows and hides over the image.
+    pixel_grid: PixelGridSettings,
+    show_pixel_grid: bool,
+    /// Preset of the shader chain, reloaded when one of its files changes.
+    preset: Option<(PathBuf, FileWatcher)>,
+    /// Why the shader chain last failed to reload, shown until it reloads.
+    error_overlay: Option<TextOverlay>,
+    show_parameters: bool,
+    selected_parameter: usize,
+    /// Parameters changed since the chain loaded or last saved them.
+    unsaved_parameters: Vec<(String, f32)>,
+    parameter_panel: Option<TextOverlay>,
     window: Window,
 }
 
-#[derive(Debug, thiserror::Error)]
-enum AppError {
-    #[error("No compatible adapter found")]
-    NoAdapterFound,
-}
-
 impl Application {
-    async fn new(window: Window) -> Result<Self, Box<dyn Error>> {
+    async fn new(
+        window: Window,
+        image: &image::RgbaImage,
+        args: &ViewArgs,
+    ) -> Result<Self, Box<dyn Error>> {
         let size = window.inner_size();
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            dx12_shader_compiler: Default::default(),
-        });
+        let instance = gpu::create_instance(args.gpu.backend);
         let surface = unsafe { instance.create_surface(&window) }?;
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptionsBase {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .ok_or(AppError::NoAdapterFound)?;
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    features: wgpu::Features::empty(),
-                    limits: wgpu::Limits::default(),
-                    label: None,
-                },
-                None,
-            )
-            .await?;
+        let (adapter, device, queue) =
+            gpu::request_device(&instance, Some(&surface), args.gpu.fallback_adapter).await?;
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps
             .formats
             .iter()
             .copied()
-            .filter(|f| f.is_srgb())
-            .next()
+            .find(|f| f.is_srgb())
             .unwrap_or(surface_caps.formats[0]);
+        // Without an sRGB format, an sRGB view of a UNORM one still lets the
+        // GPU encode colors. Only then does a shader have to.
+        let srgb_views = adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS);
+        let view_format = match surface_format.add_srgb_suffix() {
+            format if srgb_views && srgb::needs_encoding(surface_format) => format,
+            _ => surface_format,
+        };
+        if srgb::needs_encoding(view_format) {
+            log::warn!(
+                "Surface format {surface_format:?} is not sRGB, encoding colors in a shader"
+            );
+        } else {
+            log::info!("Rendering to {view_format:?} views of a {surface_format:?} surface");
+        }
+        let present_mode = match args.present_mode {
+            Some(mode) if surface_caps.present_modes.contains(&mode) => mode,
+            Some(mode) => {
+                log::warn!("Present mode {mode:?} is not supported, using the default one");
+                surface_caps.present_modes[0]
+            }
+            None => surface_caps.present_modes[0],
+        };
+        // Frames hold premultiplied colors, which only a translucent
+        // background leaves with any alpha below 1.
+        let alpha_modes = &surface_caps.alpha_modes;
+        let translucent = args.background.a < 255;
+        let alpha_mode =
+            if translucent && alpha_modes.contains(&wgpu::CompositeAlphaMode::PreMultiplied) {
+                wgpu::CompositeAlphaMode::PreMultiplied
+            } else {
+                if translucent {
+                    log::warn!("The surface can't be translucent, the background will be opaque");
+                }
+                if alpha_modes.contains(&wgpu::CompositeAlphaMode::Opaque) {
+                    wgpu::CompositeAlphaMode::Opaque
+                } else {
+                    alpha_modes[0]
+                }
+            };
+        log::info!("Compositing the surface with alpha mode {alpha_mode:?}");
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
+            present_mode,
+            alpha_mode,
+            view_formats: vec![view_format],
         };
         surface.configure(&device, &config);
+        texture::check_size(&device, PhysicalSize::new(image.width(), image.height()))?;
+        let source = Texture::from_image(&device, &queue, image, Some("Source texture"));
+        log::info!("Source texture is {}x{}", source.width, source.height);
+        let render_format = if srgb::needs_encoding(view_format) {
+            srgb::FORMAT
+        } else {
+            view_format
+        };
+        let mut renderer = Renderer::new(&device, render_format, source);
+        let source_alpha = if args.premultiplied {
+            SourceAlpha::Premultiplied
+        } else {
+            SourceAlpha::Straight
+        };
+        renderer.set_source_alpha(source_alpha);
+        renderer.set_compositing(Compositing::Blend);
+        let checkerboard = args.checkerboard.settings();
+        let show_checkerboard = args.checkerboard.checkerboard;
+        renderer.set_checkerboard(&device, show_checkerboard.then_some(checkerboard));
+        let pixel_grid = args.pixel_grid.settings();
+        let show_pixel_grid = args.pixel_grid.pixel_grid;
+        renderer.set_pixel_grid(&device, show_pixel_grid.then_some(pixel_grid));
+        let srgb_encoder = srgb::needs_encoding(view_format).then(|| {
+            SrgbEncoder::new(&device, &queue, renderer.blit_pipeline(), view_format, size)
+        });
+        let mut preset = None;
+        if let Some(path) = &args.scaling.preset {
+            renderer.load_shader_chain(&device, &queue, path)?;
+            let sources = renderer
+                .shader_chain()
+                .map(|chain| chain.sources().to_vec());
+            preset = Some((path.clone(), FileWatcher::new(sources.unwrap_or_default())));
+        }
+        let post_process = args
+            .post_process
+            .settings()
+            .map(|settings| PostProcess::new(&device, &renderer, render_format, settings));
+        let post_processes = [
+            None,
+            Some(PostProcessSettings::Crt(args.post_process.crt_settings())),
+            Some(PostProcessSettings::Lcd(args.post_process.lcd_settings())),
+        ];
+        let post_process_index = post_processes
+            .iter()
+            .position(|settings| *settings == args.post_process.settings())
+            .unwrap_or(0);
+        // Fixed scales beyond what zooming reaches would need passes larger
+        // than any texture.
+        let view = View::new(args.fractional_zoom);
+        let scaling = args.scaling.scaling();
+        let max_size = device.limits().max_texture_dimension_2d;
+        let max_zoom = view
+            .max_zoom(&scaling, renderer.source().size(), size, max_size)
+            .ok_or(ViewerError::ScaleModeTooLarge(scaling.mode, max_size))?;
+        let initial_scale = args.scaling.scale.map(|scale| scale.min(max_zoom));
+        let keymap = match &args.keymap {
+            Some(path) => Keymap::load(path)?,
+            None => Keymap::default(),
+        };
 
-        Ok(Self {
+        let mut app = Self {
             window,
             surface,
             device,
             queue,
             config,
+            view_format,
+            srgb_encoder,
+            render_format,
             size,
-        })
+            renderer,
+            post_process,
+            post_processes,
+            post_process_index,
+            scaling: Scaling {
+                scale: initial_scale,
+                ..scaling
+            },
+            initial_scale,
+            fixed_scale: initial_scale.unwrap_or(1.0),
+            view,
+            snap: args.snap,
+            snapping: false,
+            keymap,
+            background: args.background,
+            checkerboard,
+            show_checkerboard,
+            source_alpha,
+            pixel_grid,
+            show_pixel_grid,
+            preset,
+            error_overlay: None,
+            show_parameters: false,
+            selected_parameter: 0,
+            unsaved_parameters: Vec::new(),
+            parameter_panel: None,
+        };
+        app.update_layout();
+        app.snap_window();
+        Ok(app)
     }
 
     fn window(&self) -> &Window {
@@ -110,39 +268,440 @@ impl Application {
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
+            if let Some(srgb_encoder) = &mut self.srgb_encoder {
+                srgb_encoder.resize(
+                    &self.device,
+                    &self.queue,
+                    self.renderer.blit_pipeline(),
+                    new_size,
+                );
+            }
+            self.update_layout();
+        }
+    }
+
+    /// Snaps the window after a resize, unless snapping caused it.
+    fn resized(&mut self) {
+        if !std::mem::take(&mut self.snapping) {
+            self.snap_window();
+        }
+    }
+
+    /// Resizes the window to show the image at the whole factor closest to
+    /// its size, if snapping is on. The window then reports its new size.
+    fn snap_window(&mut self) {
+        if !self.snap || self.window.fullscreen().is_some() || self.window.is_maximized() {
+            return;
+        }
+        // A window larger than the monitor would get resized again.
+        let bounds = self
+            .window
+            .current_monitor()
+            .map_or(PhysicalSize::new(u32::MAX, u32::MAX), |monitor| {
+                monitor.size()
+            });
+        let snapped = self
+            .scaling
+            .snapped_size(self.renderer.source().size(), self.size, bounds);
+        if snapped != self.size {
+            self.snapping = true;
+            self.window.set_inner_size(snapped);
+        }
+    }
+
+    fn update_layout(&mut self) {
+        let source_size = self.renderer.source().size();
+        let max_size = self.device.limits().max_texture_dimension_2d;
+        let mut layout = self.view.layout(&self.scaling, source_size, self.size);
+        if !layout.fits(source_size, max_size) {
+            // Fitting a large window or going back to a fixed scale can call
+            // for passes larger than any texture, so those zoom out instead.
+            let Some(max_zoom) =
+                self.view
+                    .max_zoom(&self.scaling, source_size, self.size, max_size)
+            else {
+                let error = ViewerError::ScaleModeTooLarge(self.scaling.mode, max_size);
+                log::error!("{error}");
+                return;
+            };
+            self.scaling.scale = Some(max_zoom);
+            layout = self.view.layout(&self.scaling, source_size, self.size);
+        }
+        let viewport = layout.viewport;
+        self.renderer
+            .set_layout(&self.device, &self.queue, layout, self.size);
+        let background = self.clear_color();
+        if let Some(post_process) = &mut self.post_process {
+            post_process.set_layout(
+                &self.device,
+                &self.queue,
+                &self.renderer,
+                viewport,
+                self.size,
+                background,
+            );
+        }
+        for overlay in [&self.error_overlay, &self.parameter_panel]
+            .into_iter()
+            .flatten()
+        {
+            overlay.set_layout(&self.queue, self.size);
         }
     }
 
-    fn input(&mut self, _event: &WindowEvent) -> bool {
-        false
+    /// The background as render targets take it, premultiplied like
+    /// everything drawn over it.
+    fn clear_color(&self) -> wgpu::Color {
+        self.background.to_linear().premultiplied().to_wgpu()
+    }
+
+    /// Reloads the shader chain if one of its files changed. Keeps the last
+    /// chain that loaded if the new one fails, and shows why over the image.
+    fn reload_shader_chain(&mut self) {
+        let Some((path, watcher)) = &mut self.preset else {
+            return;
+        };
+        if !watcher.poll() {
+            return;
+        }
+        let result = self
+            .renderer
+            .load_shader_chain(&self.device, &self.queue, path);
+        let mut sources = self
+            .renderer
+            .shader_chain()
+            .map(|chain| chain.sources().to_vec())
+            .unwrap_or_default();
+        match &result {
+            Ok(()) => log::info!("Reloaded {}", path.display()),
+            // The last chain that loaded may not have read the broken file.
+            Err(error) => sources.extend(error.path().map(ToOwned::to_owned)),
+        }
+        watcher.set_paths(sources);
+        match result {
+            Ok(()) => {
+                self.error_overlay = None;
+                // Tweaks survive reloads until they are saved.
+                if let Some(chain) = self.renderer.shader_chain_mut() {
+                    for (name, value) in &self.unsaved_parameters {
+                        let index = chain.parameters().iter().position(|p| &p.name == name);
+                        if let Some(index) = index {
+                            chain.set_parameter(index, *value);
+                        }
+                    }
+                }
+            }
+            Err(error) => self.show_error(&error.to_string()),
+        }
+        self.update_parameter_panel();
+        self.update_layout();
+    }
+
+    /// Logs `message` and shows it over the image until the shader chain
+    /// next reloads.
+    fn show_error(&mut self, message: &str) {
+        log::error!("{message}");
+        let error_overlay = TextOverlay::new(
+            &self.device,
+            &self.queue,
+            self.renderer.blit_pipeline(),
+            self.render_format,
+            Corner::TopLeft,
+            message,
+        );
+        error_overlay.set_layout(&self.queue, self.size);
+        self.error_overlay = Some(error_overlay);
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => self.parameter_input(*key) || self.key_input(*key),
+            WindowEvent::MouseWheel { delta, .. } => {
+                let steps = match *delta {
+                    // Some mice also send zero deltas, which mustn't zoom.
+                    MouseScrollDelta::LineDelta(_, lines) if lines > 0.0 => 1,
+                    MouseScrollDelta::LineDelta(_, lines) if lines < 0.0 => -1,
+                    MouseScrollDelta::LineDelta(..) => 0,
+                    MouseScrollDelta::PixelDelta(pixels) => self.view.scroll_steps(pixels.y),
+                };
+                if steps != 0 {
+                    self.zoom(steps, self.view.cursor());
+                    self.update_layout();
+                }
+                true
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.view.set_dragging(*state == ElementState::Pressed);
+                true
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                if self.view.cursor_moved(*position) {
+                    self.update_layout();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Zooms `steps` levels in, or out if negative, around `anchor`.
+    fn zoom(&mut self, steps: i32, anchor: PhysicalPosition<f64>) {
+        let source_size = self.renderer.source().size();
+        self.view.zoom(
+            &mut self.scaling,
+            steps,
+            source_size,
+            self.size,
+            self.device.limits().max_texture_dimension_2d,
+            anchor,
+        );
+    }
+
+    /// Keys bound to an [`Action`] in the keymap.
+    fn key_input(&mut self, key: VirtualKeyCode) -> bool {
+        let Some(action) = self.keymap.action(key) else {
+            return false;
+        };
+        let center =
+            PhysicalPosition::new(self.size.width as f64 / 2.0, self.size.height as f64 / 2.0);
+        match action {
+            Action::ZoomIn => self.zoom(1, center),
+            Action::ZoomOut => self.zoom(-1, center),
+            Action::ToggleFit => {
+                match self.scaling.scale.take() {
+                    Some(scale) => self.fixed_scale = scale,
+                    None => self.scaling.scale = Some(self.fixed_scale),
+                }
+                self.view.reset();
+            }
+            Action::Mode(mode) => {
+                let max_size = self.device.limits().max_texture_dimension_2d;
+                let scaling = Scaling {
+                    mode,
+                    ..self.scaling
+                };
+                let source_size = self.renderer.source().size();
+                if self
+                    .view
+                    .max_zoom(&scaling, source_size, self.size, max_size)
+                    .is_none()
+                {
+                    log::warn!("{}", ViewerError::ScaleModeTooLarge(mode, max_size));
+                    return true;
+                }
+                self.scaling.mode = mode;
+                self.snap_window();
+            }
+            Action::CyclePostProcess => {
+                self.post_process_index = (self.post_process_index + 1) % self.post_processes.len();
+                self.post_process = self.post_processes[self.post_process_index].map(|settings| {
+                    PostProcess::new(&self.device, &self.renderer, self.render_format, settings)
+                });
+            }
+            Action::ToggleSnap => {
+                self.snap = !self.snap;
+                self.snap_window();
+                return true;
+            }
+            Action::ResetView => {
+                self.scaling.scale = self.initial_scale;
+                self.view.reset();
+            }
+            Action::ToggleFullscreen(mode) => {
+                let fullscreen = match self.window.fullscreen() {
+                    Some(fullscreen) if mode.matches(&fullscreen) => None,
+                    _ => Some(mode.fullscreen(
+                        self.window.current_monitor(),
+                        &self.scaling,
+                        self.renderer.source().size(),
+                    )),
+                };
+                // The window then reports its new size.
+                self.window.set_fullscreen(fullscreen);
+                return true;
+            }
+            Action::ShowParameters => {
+                if self.renderer.shader_chain().is_none() {
+                    return false;
+                }
+                self.show_parameters = !self.show_parameters;
+                self.update_parameter_panel();
+                return true;
+            }
+            Action::ToggleCheckerboard => {
+                self.show_checkerboard = !self.show_checkerboard;
+                self.renderer.set_checkerboard(
+                    &self.device,
+                    self.show_checkerboard.then_some(self.checkerboard),
+                );
+            }
+            Action::TogglePremultiplied => {
+                self.source_alpha = match self.source_alpha {
+                    SourceAlpha::Straight => SourceAlpha::Premultiplied,
+                    SourceAlpha::Premultiplied => SourceAlpha::Straight,
+                };
+                self.renderer.set_source_alpha(self.source_alpha);
+            }
+            Action::TogglePixelGrid => {
+                self.show_pixel_grid = !self.show_pixel_grid;
+                self.renderer.set_pixel_grid(
+                    &self.device,
+                    self.show_pixel_grid.then_some(self.pixel_grid),
+                );
+            }
+        }
+        self.update_layout();
+        true
+    }
+
+    /// Keys of the shader parameter panel while it shows: Up and Down select
+    /// a parameter, Left and Right step it, Back resets it, S saves the
+    /// parameters to the preset and Escape hides the panel.
+    fn parameter_input(&mut self, key: VirtualKeyCode) -> bool {
+        if !self.show_parameters {
+            return false;
+        }
+        let Some(chain) = self.renderer.shader_chain_mut() else {
+            return false;
+        };
+        let count = chain.parameters().len();
+        if key == VirtualKeyCode::Escape {
+            self.show_parameters = false;
+        } else if key == VirtualKeyCode::S {
+            match chain.save_parameters() {
+                Ok(()) => {
+                    log::info!("Saved the shader parameters");
+                    self.unsaved_parameters.clear();
+                }
+                Err(error) => self.show_error(&error.to_string()),
+            }
+        } else if count > 0 {
+            let selected = self.selected_parameter.min(count - 1);
+            let parameter = &chain.parameters()[selected];
+            let value = match key {
+                VirtualKeyCode::Up => {
+                    self.selected_parameter = (selected + count - 1) % count;
+                    None
+                }
+                VirtualKeyCode::Down => {
+                    self.selected_parameter = (selected + 1) % count;
+                    None
+                }
+                VirtualKeyCode::Left => Some(parameter.stepped(-1)),
+                VirtualKeyCode::Right => Some(parameter.stepped(1)),
+                VirtualKeyCode::Back => Some(parameter.default),
+                _ => return false,
+            };
+            if let Some(value) = value {
+                chain.set_parameter(selected, value);
+                let parameter = &chain.parameters()[selected];
+                self.unsaved_parameters
+                    .retain(|(name, _)| *name != parameter.name);
+                self.unsaved_parameters
+                    .push((parameter.name.clone(), parameter.value));
+            }
+        } else {
+            return false;
+        }
+        self.update_parameter_panel();
+        true
+    }
+
+    /// Rebuilds the shader parameter panel after anything it shows changed.
+    fn update_parameter_panel(&mut self) {
+        self.parameter_panel = None;
+        let Some(chain) = self
+            .renderer
+            .shader_chain()
+            .filter(|_| self.show_parameters)
+        else {
+            return;
+        };
+        let mut text = String::from(
+            "Shader parameters: Up/Down select, Left/Right change, Backspace resets, S saves",
+        );
+        if chain.parameters().is_empty() {
+            text.push_str("\n  The shaders have no parameters");
+        }
+        let selected = self
+            .selected_parameter
+            .min(chain.parameters().len().saturating_sub(1));
+        for (i, parameter) in chain.parameters().iter().enumerate() {
+            let marker = if i == selected { '>' } else { ' ' };
+            let label = if parameter.description.is_empty() {
+                &parameter.name
+            } else {
+                &parameter.description
+            };
+            text.push_str(&format!(
+                "\n{marker} {label}: {:.*}",
+                parameter.decimals(),
+                parameter.value
+            ));
+        }
+        let parameter_panel = TextOverlay::new(
+            &self.device,
+            &self.queue,
+            self.renderer.blit_pipeline(),
+            self.render_format,
+            Corner::BottomLeft,
+            &text,
+        );
+        parameter_panel.set_layout(&self.queue, self.size);
+        self.parameter_panel = Some(parameter_panel);
     }
 
     fn update(&mut self) {
-        // Intentionally left empty for now.
+        self.reload_shader_chain();
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let surface_view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(self.view_format),
+            ..Default::default()
+        });
+        let view = match &self.srgb_encoder {
+            Some(srgb_encoder) => srgb_encoder.view(),
+            None => &surface_view,
+        };
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render encoder"),
             });
-        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(CORNFLOWER_BLUE.to_wgpu()),
-                    store: true,
-                },
-            })],
-            depth_stencil_attachment: None,
-        });
+        let background = self.clear_color();
+        match &mut self.post_process {
+            Some(post_process) => {
+                self.renderer
+                    .render(&self.queue, &mut encoder, post_process.input(), background);
+                post_process.render(&self.queue, &mut encoder, view);
+            }
+            None => self
+                .renderer
+                .render(&self.queue, &mut encoder, view, background),
+        }
+        for overlay in [&self.error_overlay, &self.parameter_panel]
+            .into_iter()
+            .flatten()
+        {
+            overlay.render(&mut encoder, view);
+        }
+        if let Some(srgb_encoder) = &self.srgb_encoder {
+            srgb_encoder.render(&mut encoder, &surface_view);
+        }
 
         self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
@@ -151,18 +710,29 @@ impl Application {
     }
 }
 
-pub async fn run() -> Result<(), Box<dyn Error>> {
+pub async fn run(args: ViewArgs) -> Result<(), Box<dyn Error>> {
     env_logger::init();
+    let image = crate::texture::load_image(&args.input)?;
     let event_loop = EventLoop::new();
+    let monitor = event_loop
+        .primary_monitor()
+        .or_else(|| event_loop.available_monitors().next());
+    let source_size = PhysicalSize::new(image.width(), image.height());
+    let fullscreen = args
+        .fullscreen
+        .map(|mode| mode.fullscreen(monitor, &args.scaling.scaling(), source_size));
     let window = WindowBuilder::new()
-        .with_inner_size(LogicalSize::new(640, 480))
+        .with_inner_size(args.size)
+        .with_fullscreen(fullscreen)
         .with_decorations(true)
         .with_resizable(true)
         .with_active(true)
+        .with_transparent(args.background.a < 255)
         .with_title("Perfect Scale")
         .with_visible(false)
         .build(&event_loop)?;
-    let mut app = Application::new(window).await?;
+    let mut app = Application::new(window, &image, &args).await?;
+    app.window().set_visible(true);
 
     event_loop.run(move |event, _, control_flow| {
         control_flow.set_poll();
@@ -182,7 +752,10 @@ pub async fn run() -> Result<(), Box<dyn Error>> {
                             },
                         ..
                     } => *control_flow = ControlFlow::Exit,
-                    WindowEvent::Resized(size) => app.resize(size),
+                    WindowEvent::Resized(size) => {
+                        app.resize(size);
+                        app.resized();
+                    }
                     WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
                         app.resize(*new_inner_size);
                     }
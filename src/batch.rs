@@ -0,0 +1,251 @@
+use crate::{cli::BatchArgs, headless::OffscreenRenderer, texture};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs, io,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+const EXTENSIONS: [&str; 3] = ["png", "bmp", "qoi"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum BatchError {
+    #[error("{0} of {1} images failed")]
+    Failed(usize, usize),
+    #[error("Another image would also be saved to {}", .0.display())]
+    OutputClash(PathBuf),
+}
+
+/// Scales every image under the input directory. Decoding and encoding run
+/// on `jobs` threads each while this thread feeds the GPU one image at a time.
+pub async fn run(args: BatchArgs) -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    let started = Instant::now();
+    let mut files = Vec::new();
+    collect_images(&args.input, &mut files)?;
+    files.sort();
+    let total = files.len();
+    let jobs = args.jobs.map_or_else(
+        || thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        NonZeroUsize::get,
+    );
+    let mut renderer = OffscreenRenderer::new(
+        &args.gpu,
+        args.scaling.preset.as_deref(),
+        args.post_process.settings(),
+    )
+    .await?;
+    let scaling = args.scaling.scaling();
+
+    let failed = AtomicUsize::new(0);
+    let report = |path: &Path, error: &dyn Error| {
+        failed.fetch_add(1, Ordering::Relaxed);
+        eprintln!("{}: {error}", path.display());
+    };
+    let (files, clashing) = split_clashing(&args, files);
+    for (path, output) in clashing {
+        report(&path, &BatchError::OutputClash(output));
+    }
+    let pending = Mutex::new(files.into_iter());
+
+    let (decoded_sender, decoded) = mpsc::sync_channel(jobs);
+    let (rendered_sender, rendered) = mpsc::sync_channel::<(PathBuf, image::RgbaImage)>(jobs);
+    let rendered = Mutex::new(rendered);
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let decoded_sender = decoded_sender.clone();
+            let pending = &pending;
+            scope.spawn(move || loop {
+                let Some(path) = pending.lock().unwrap().next() else {
+                    break;
+                };
+                let image = texture::load_image(&path);
+                if decoded_sender.send((path, image)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(decoded_sender);
+
+        for _ in 0..jobs {
+            let rendered = &rendered;
+            let report = &report;
+            let args = &args;
+            scope.spawn(move || loop {
+                let Ok((path, image)) = rendered.lock().unwrap().recv() else {
+                    break;
+                };
+                if let Err(error) = save(args, &path, &image) {
+                    report(&path, &*error);
+                }
+            });
+        }
+
+        for (path, image) in decoded {
+            let result = image
+                .map_err(Into::into)
+                .and_then(|image| renderer.render(&image, scaling, args.size, args.background));
+            match result {
+                Ok(image) => rendered_sender.send((path, image)).unwrap(),
+                Err(error) => report(&path, &*error),
+            }
+        }
+        drop(rendered_sender);
+    });
+
+    let failed = failed.into_inner();
+    println!(
+        "Scaled {} of {total} images in {:.2?}",
+        total - failed,
+        started.elapsed()
+    );
+    if failed > 0 {
+        return Err(BatchError::Failed(failed, total).into());
+    }
+    Ok(())
+}
+
+/// Adds the images under `dir` to `files`. Doesn't follow symlinks to
+/// directories, which could loop back up the tree.
+fn collect_images(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_images(&path, files)?;
+        } else if path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                EXTENSIONS
+                    .iter()
+                    .any(|known| extension.eq_ignore_ascii_case(known))
+            })
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Where the scaled image of `path`, under the input directory, is saved.
+fn output_path(args: &BatchArgs, path: &Path) -> PathBuf {
+    let relative = path
+        .strip_prefix(&args.input)
+        .expect("images are collected under the input directory");
+    args.output.join(relative).with_extension(&args.extension)
+}
+
+/// Splits off the images that would be saved to the same path as another,
+/// like `hero.png` and `hero.bmp`, with that path. Saving them would
+/// overwrite all but one.
+fn split_clashing(
+    args: &BatchArgs,
+    files: Vec<PathBuf>,
+) -> (Vec<PathBuf>, Vec<(PathBuf, PathBuf)>) {
+    let outputs: Vec<_> = files.iter().map(|path| output_path(args, path)).collect();
+    let mut counts = HashMap::<&Path, usize>::new();
+    for output in &outputs {
+        *counts.entry(output).or_default() += 1;
+    }
+    let mut kept = Vec::new();
+    let mut clashing = Vec::new();
+    for (path, output) in files.into_iter().zip(&outputs) {
+        if counts[output.as_path()] > 1 {
+            clashing.push((path, output.clone()));
+        } else {
+            kept.push(path);
+        }
+    }
+    (kept, clashing)
+}
+
+fn save(
+    args: &BatchArgs,
+    path: &Path,
+    image: &image::RgbaImage,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let output = output_path(args, path);
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    image.save(output)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command};
+    use clap::Parser;
+
+    #[test]
+    fn collects_images_in_every_subdirectory() {
+        let dir = std::env::temp_dir().join(format!("perfect-scale-batch-{}", std::process::id()));
+        for file in [
+            "title.png",
+            "notes.txt",
+            "sprites/hero.BMP",
+            "sprites/hero.png.bak",
+            "sprites/tiles/grass.qoi",
+            "sprites/tiles/README",
+            "empty/.png",
+        ] {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, dir.join("sprites/loop")).unwrap();
+
+        let mut files = Vec::new();
+        collect_images(&dir, &mut files).unwrap();
+        files.sort();
+        fs::remove_dir_all(&dir).unwrap();
+        let expected: Vec<_> = ["sprites/hero.BMP", "sprites/tiles/grass.qoi", "title.png"]
+            .iter()
+            .map(|file| dir.join(file))
+            .collect();
+        assert_eq!(files, expected);
+    }
+
+    #[test]
+    fn splits_off_images_saved_to_the_same_path() {
+        let cli = Cli::try_parse_from(["perfect-scale", "batch", "in", "out"]).unwrap();
+        let Some(Command::Batch(args)) = cli.command else {
+            panic!("expected the batch command");
+        };
+        let files = [
+            "in/hero.bmp",
+            "in/hero.png",
+            "in/sprites/hero.png",
+            "in/title.qoi",
+        ]
+        .map(PathBuf::from)
+        .to_vec();
+        let (files, clashing) = split_clashing(&args, files);
+        assert_eq!(
+            files,
+            [
+                PathBuf::from("in/sprites/hero.png"),
+                PathBuf::from("in/title.qoi")
+            ]
+        );
+        let output = PathBuf::from("out/hero.png");
+        assert_eq!(
+            clashing,
+            [
+                (PathBuf::from("in/hero.bmp"), output.clone()),
+                (PathBuf::from("in/hero.png"), output)
+            ]
+        );
+    }
+}
@@ -0,0 +1,463 @@
+use crate::{scaling::Viewport, texture::Texture};
+use winit::dpi::PhysicalSize;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct BlitUniforms {
+    viewport: [f32; 4],
+    target_size: [f32; 2],
+    _padding: [f32; 2],
+}
+
+impl BlitUniforms {
+    fn new(viewport: Viewport, target_size: PhysicalSize<u32>) -> Self {
+        Self {
+            viewport: [
+                viewport.x as f32,
+                viewport.y as f32,
+                viewport.width as f32,
+                viewport.height as f32,
+            ],
+            target_size: [target_size.width as f32, target_size.height as f32],
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+/// Every fragment stage shares the vertex stage and bindings in blit.wgsl, so
+/// they are all entry points of one module.
+const SHADER: &str = concat!(
+    include_str!("shaders/blit.wgsl"),
+    include_str!("shaders/epx.wgsl"),
+    include_str!("shaders/xbrz.wgsl"),
+    include_str!("shaders/hqx.wgsl"),
+    include_str!("shaders/crt.wgsl"),
+    include_str!("shaders/lcd.wgsl"),
+    include_str!("shaders/checkerboard.wgsl"),
+    include_str!("shaders/pixel_grid.wgsl"),
+);
+
+/// What a stage binds at group 1, after the source at group 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraBindings {
+    None,
+    /// A texture read with `textureLoad`, see [`Blit::with_lookup_table`].
+    LookupTable,
+    /// A uniform buffer, see [`Blit::with_parameters`].
+    Parameters,
+    /// A uniform buffer and the previous frame, see [`Blit::with_history`].
+    History,
+}
+
+/// Shader and layouts for copying a texture into a rectangle of a render target.
+pub struct BlitPipeline {
+    shader: wgpu::ShaderModule,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    lookup_table_layout: wgpu::BindGroupLayout,
+    lookup_table_pipeline_layout: wgpu::PipelineLayout,
+    parameters_layout: wgpu::BindGroupLayout,
+    parameters_pipeline_layout: wgpu::PipelineLayout,
+    history_layout: wgpu::BindGroupLayout,
+    history_pipeline_layout: wgpu::PipelineLayout,
+}
+
+impl BlitPipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Blit shader"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Blit bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blit pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let (lookup_table_layout, lookup_table_pipeline_layout) = extra_layouts(
+            device,
+            &bind_group_layout,
+            "Lookup table",
+            &[texture_entry(0)],
+        );
+        let (parameters_layout, parameters_pipeline_layout) = extra_layouts(
+            device,
+            &bind_group_layout,
+            "Parameters",
+            &[uniform_entry(0)],
+        );
+        let (history_layout, history_pipeline_layout) = extra_layouts(
+            device,
+            &bind_group_layout,
+            "History",
+            &[uniform_entry(0), texture_entry(1)],
+        );
+
+        Self {
+            shader,
+            bind_group_layout,
+            pipeline_layout,
+            lookup_table_layout,
+            lookup_table_pipeline_layout,
+            parameters_layout,
+            parameters_pipeline_layout,
+            history_layout,
+            history_pipeline_layout,
+        }
+    }
+
+    /// Pipelines for stages with extra bindings must be drawn with a [`Blit`]
+    /// that binds them.
+    pub fn create_pipeline(
+        &self,
+        device: &wgpu::Device,
+        target_format: wgpu::TextureFormat,
+        fragment_entry_point: &str,
+        extra_bindings: ExtraBindings,
+    ) -> wgpu::RenderPipeline {
+        self.create_pipeline_with_shader(
+            device,
+            &self.shader,
+            target_format,
+            fragment_entry_point,
+            extra_bindings,
+        )
+    }
+
+    /// Like [`BlitPipeline::create_pipeline`], blending what the stage draws
+    /// with what the target holds instead of replacing it.
+    pub fn create_blended_pipeline(
+        &self,
+        device: &wgpu::Device,
+        target_format: wgpu::TextureFormat,
+        fragment_entry_point: &str,
+        extra_bindings: ExtraBindings,
+        blend: wgpu::BlendState,
+    ) -> wgpu::RenderPipeline {
+        self.create_render_pipeline(
+            device,
+            &self.shader,
+            target_format,
+            fragment_entry_point,
+            extra_bindings,
+            blend,
+        )
+    }
+
+    /// Like [`BlitPipeline::create_pipeline`], for a fragment stage in another
+    /// module that shares the vertex stage and bindings of blit.wgsl.
+    pub fn create_pipeline_with_shader(
+        &self,
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        target_format: wgpu::TextureFormat,
+        fragment_entry_point: &str,
+        extra_bindings: ExtraBindings,
+    ) -> wgpu::RenderPipeline {
+        self.create_render_pipeline(
+            device,
+            shader,
+            target_format,
+            fragment_entry_point,
+            extra_bindings,
+            wgpu::BlendState::REPLACE,
+        )
+    }
+
+    fn create_render_pipeline(
+        &self,
+        device: &wgpu::Device,
+        shader: &wgpu::ShaderModule,
+        target_format: wgpu::TextureFormat,
+        fragment_entry_point: &str,
+        extra_bindings: ExtraBindings,
+        blend: wgpu::BlendState,
+    ) -> wgpu::RenderPipeline {
+        let layout = match extra_bindings {
+            ExtraBindings::None => &self.pipeline_layout,
+            ExtraBindings::LookupTable => &self.lookup_table_pipeline_layout,
+            ExtraBindings::Parameters => &self.parameters_pipeline_layout,
+            ExtraBindings::History => &self.history_pipeline_layout,
+        };
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Blit pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: fragment_entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: target_format,
+                    blend: Some(blend),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    }
+}
+
+/// A texture and sampler bound for drawing through a [`BlitPipeline`].
+pub struct Blit {
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+    extra: Option<wgpu::BindGroup>,
+}
+
+impl Blit {
+    pub fn new(
+        device: &wgpu::Device,
+        pipeline: &BlitPipeline,
+        view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+    ) -> Self {
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Blit uniform buffer"),
+            size: std::mem::size_of::<BlitUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Blit bind group"),
+            layout: &pipeline.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+
+        Self {
+            bind_group,
+            uniform_buffer,
+            extra: None,
+        }
+    }
+
+    /// For stages that compute their colors from a uniform buffer of
+    /// parameters alone. They read no texture, but one still has to be bound.
+    pub fn procedural(
+        device: &wgpu::Device,
+        pipeline: &BlitPipeline,
+        parameters: &wgpu::Buffer,
+        label: &str,
+    ) -> Self {
+        let texture = Texture::render_target(
+            device,
+            PhysicalSize::new(1, 1),
+            wgpu::TextureFormat::Rgba8Unorm,
+            Some(&format!("{label} texture")),
+        );
+        let sampler = create_sampler(device, wgpu::FilterMode::Nearest);
+        Self::new(device, pipeline, &texture.view, &sampler)
+            .with_parameters(device, pipeline, parameters)
+    }
+
+    /// Also binds a lookup table, for stages that read one.
+    pub fn with_lookup_table(
+        mut self,
+        device: &wgpu::Device,
+        pipeline: &BlitPipeline,
+        lookup_table: &wgpu::TextureView,
+    ) -> Self {
+        self.extra = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Lookup table bind group"),
+            layout: &pipeline.lookup_table_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(lookup_table),
+            }],
+        }));
+        self
+    }
+
+    /// Also binds a uniform buffer, for stages that take parameters.
+    pub fn with_parameters(
+        mut self,
+        device: &wgpu::Device,
+        pipeline: &BlitPipeline,
+        parameters: &wgpu::Buffer,
+    ) -> Self {
+        self.extra = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Parameters bind group"),
+            layout: &pipeline.parameters_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: parameters.as_entire_binding(),
+            }],
+        }));
+        self
+    }
+
+    /// Also binds a uniform buffer and the texture drawn on the previous frame,
+    /// for stages that blend with it.
+    pub fn with_history(
+        mut self,
+        device: &wgpu::Device,
+        pipeline: &BlitPipeline,
+        parameters: &wgpu::Buffer,
+        history: &wgpu::TextureView,
+    ) -> Self {
+        self.extra = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("History bind group"),
+            layout: &pipeline.history_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: parameters.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(history),
+                },
+            ],
+        }));
+        self
+    }
+
+    pub fn set_viewport(
+        &self,
+        queue: &wgpu::Queue,
+        viewport: Viewport,
+        target_size: PhysicalSize<u32>,
+    ) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&BlitUniforms::new(viewport, target_size)),
+        );
+    }
+
+    pub fn draw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        pipeline: &'a wgpu::RenderPipeline,
+    ) {
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        if let Some(extra) = &self.extra {
+            render_pass.set_bind_group(1, extra, &[]);
+        }
+        render_pass.draw(0..4, 0..1);
+    }
+}
+
+/// Layout of a stage's extra bind group, and of the pipelines that use it.
+fn extra_layouts(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    label: &str,
+    entries: &[wgpu::BindGroupLayoutEntry],
+) -> (wgpu::BindGroupLayout, wgpu::PipelineLayout) {
+    let extra_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(&format!("{label} bind group layout")),
+        entries,
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(&format!("{label} pipeline layout")),
+        bind_group_layouts: &[bind_group_layout, &extra_layout],
+        push_constant_ranges: &[],
+    });
+    (extra_layout, pipeline_layout)
+}
+
+/// Fragment stage uniform buffer.
+fn uniform_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+/// Fragment stage texture read with `textureLoad`.
+fn texture_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: false },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+pub fn create_sampler(device: &wgpu::Device, filter: wgpu::FilterMode) -> wgpu::Sampler {
+    create_wrapping_sampler(device, filter, wgpu::AddressMode::ClampToEdge)
+}
+
+/// Like [`create_sampler`], with what to sample outside of the texture.
+pub fn create_wrapping_sampler(
+    device: &wgpu::Device,
+    filter: wgpu::FilterMode,
+    address_mode: wgpu::AddressMode,
+) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Blit sampler"),
+        address_mode_u: address_mode,
+        address_mode_v: address_mode,
+        address_mode_w: address_mode,
+        mag_filter: filter,
+        min_filter: filter,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        border_color: (address_mode == wgpu::AddressMode::ClampToBorder)
+            .then_some(wgpu::SamplerBorderColor::TransparentBlack),
+        ..Default::default()
+    })
+}
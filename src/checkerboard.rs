@@ -0,0 +1,123 @@
+use crate::{
+    blit::{Blit, BlitPipeline, ExtraBindings},
+    color::U8Color,
+    scaling::Viewport,
+};
+use std::str::FromStr;
+use winit::dpi::PhysicalSize;
+
+/// What the size of the checkerboard squares is measured in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CheckerUnit {
+    /// Pixels of the source image, so that the squares scale with it.
+    Source,
+    /// Pixels of the screen, so that the squares keep their size at any zoom.
+    #[default]
+    Screen,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown checkerboard unit \"{0}\", expected source or screen")]
+pub struct ParseCheckerUnitError(String);
+
+impl FromStr for CheckerUnit {
+    type Err = ParseCheckerUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "source" => Ok(Self::Source),
+            "screen" => Ok(Self::Screen),
+            _ => Err(ParseCheckerUnitError(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CheckerboardSettings {
+    /// Width and height of a square, in `unit`s, at least 1.
+    pub size: u32,
+    pub unit: CheckerUnit,
+    /// Colors of the squares, the top-left one first.
+    pub colors: [U8Color; 2],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct CheckerboardUniforms {
+    colors: [[f32; 4]; 2],
+    square_size: [f32; 2],
+    _padding: [f32; 2],
+}
+
+/// Checkerboard drawn behind the scaled image, covering the same rectangle, so
+/// that its transparent parts show as such.
+pub struct Checkerboard {
+    settings: CheckerboardSettings,
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    blit: Blit,
+}
+
+impl Checkerboard {
+    pub fn new(
+        device: &wgpu::Device,
+        blit_pipeline: &BlitPipeline,
+        target_format: wgpu::TextureFormat,
+        settings: CheckerboardSettings,
+    ) -> Self {
+        // The colors are premultiplied, to blend over a translucent background.
+        let pipeline = blit_pipeline.create_blended_pipeline(
+            device,
+            target_format,
+            "fs_checkerboard",
+            ExtraBindings::Parameters,
+            wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        );
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Checkerboard uniform buffer"),
+            size: std::mem::size_of::<CheckerboardUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let blit = Blit::procedural(device, blit_pipeline, &uniform_buffer, "Checkerboard");
+
+        Self {
+            settings,
+            pipeline,
+            uniform_buffer,
+            blit,
+        }
+    }
+
+    /// Covers `viewport`, where the source is scaled to, with squares.
+    pub fn set_layout(
+        &self,
+        queue: &wgpu::Queue,
+        source: PhysicalSize<u32>,
+        viewport: Viewport,
+        target_size: PhysicalSize<u32>,
+    ) {
+        let size = self.settings.size as f32;
+        let square_size = match self.settings.unit {
+            CheckerUnit::Source => [
+                size * viewport.width as f32 / source.width as f32,
+                size * viewport.height as f32 / source.height as f32,
+            ],
+            CheckerUnit::Screen => [size; 2],
+        };
+        let uniforms = CheckerboardUniforms {
+            colors: self
+                .settings
+                .colors
+                .map(|color| color.to_linear().premultiplied().to_array()),
+            square_size,
+            _padding: [0.0; 2],
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+        self.blit.set_viewport(queue, viewport, target_size);
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        self.blit.draw(render_pass, &self.pipeline);
+    }
+}
@@ -0,0 +1,540 @@
+use crate::{
+    checkerboard::{CheckerUnit, CheckerboardSettings},
+    color::{U8Color, CORNFLOWER_BLUE},
+    crt::{CrtSettings, Mask},
+    fullscreen::FullscreenMode,
+    lcd::LcdSettings,
+    pixel_grid::PixelGridSettings,
+    post_process::PostProcessSettings,
+    scaling::{PixelAspect, ScaleMode, Scaling},
+};
+use clap::{Args, Parser, Subcommand};
+use std::{num::NonZeroUsize, path::PathBuf};
+use winit::dpi::{LogicalSize, PhysicalSize};
+
+/// Views images scaled so that every source pixel stays crisp.
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub view: Option<ViewArgs>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Render a scaled image to a file without opening a window.
+    Render(RenderArgs),
+    /// Render every image in a directory tree into a mirrored output tree.
+    Batch(BatchArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ViewArgs {
+    /// Image to display (PNG, BMP or QOI).
+    // clap leaves the group of a struct with flattened fields empty, and
+    // without a member it never tells that viewer arguments were given.
+    #[arg(group = "ViewArgs")]
+    pub input: PathBuf,
+
+    /// Initial window size in logical pixels.
+    #[arg(long, value_name = "WIDTHxHEIGHT", default_value = "640x480", value_parser = parse_size)]
+    pub size: LogicalSize<u32>,
+
+    /// Start in fullscreen: borderless, the default, or exclusive in the
+    /// largest video mode that the image fills at a whole factor.
+    #[arg(
+        long,
+        short,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "borderless"
+    )]
+    pub fullscreen: Option<FullscreenMode>,
+
+    #[command(flatten)]
+    pub scaling: ScalingArgs,
+
+    /// Snap the window to the nearest size that shows the image at a whole
+    /// factor whenever it is resized, so that there is never a border.
+    #[arg(long)]
+    pub snap: bool,
+
+    /// Let the mouse wheel also zoom in quarter steps between whole factors,
+    /// in the modes that can draw them (sharp-bilinear and coverage).
+    #[arg(long)]
+    pub fractional_zoom: bool,
+
+    /// File of key bindings replacing the defaults, with lines like
+    /// `zoom-in = plus, numpadadd`. Actions: zoom-in, zoom-out, toggle-fit,
+    /// mode-<MODE>, cycle-post-process, reset-view, snap, fullscreen,
+    /// exclusive-fullscreen, parameters, checkerboard, premultiplied and
+    /// pixel-grid. By default +/- zoom, F toggles fitting the window, 1 to 7
+    /// pick the scaling mode, Tab cycles the CRT and LCD shaders, R resets the
+    /// view, W toggles snapping the window, F11 and F12 toggle borderless and
+    /// exclusive fullscreen, P shows the shader parameters, C toggles the
+    /// checkerboard, A toggles premultiplied alpha and G toggles the pixel
+    /// grid.
+    #[arg(long)]
+    pub keymap: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub post_process: PostProcessArgs,
+
+    /// Color of the borders around the image: #rrggbb, #rrggbbaa, rgb(),
+    /// hsl() or a CSS color name. A translucent one makes the window
+    /// translucent where the platform allows it.
+    #[arg(long, short, default_value_t = CORNFLOWER_BLUE)]
+    pub background: U8Color,
+
+    #[command(flatten)]
+    pub checkerboard: CheckerboardArgs,
+
+    /// Take the colors of the image as already multiplied by its alpha,
+    /// instead of the straight alpha that image files store.
+    #[arg(long)]
+    pub premultiplied: bool,
+
+    #[command(flatten)]
+    pub pixel_grid: PixelGridArgs,
+
+    /// Presentation mode: auto-vsync, auto-no-vsync, fifo, fifo-relaxed,
+    /// immediate or mailbox. Defaults to the first one the surface supports.
+    #[arg(long, value_parser = parse_present_mode)]
+    pub present_mode: Option<wgpu::PresentMode>,
+
+    #[command(flatten)]
+    pub gpu: GpuArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct RenderArgs {
+    /// Image to scale (PNG, BMP or QOI).
+    pub input: PathBuf,
+
+    /// File to write the scaled image to. The format follows the extension.
+    #[arg(long, short)]
+    pub output: PathBuf,
+
+    /// Size of the output image. Defaults to exactly the size of the scaled
+    /// image, at a scale of 1 unless --scale is given.
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_physical_size)]
+    pub size: Option<PhysicalSize<u32>>,
+
+    #[command(flatten)]
+    pub scaling: ScalingArgs,
+
+    #[command(flatten)]
+    pub post_process: PostProcessArgs,
+
+    /// Color of the borders around the image: #rrggbb, #rrggbbaa, rgb(),
+    /// hsl() or a CSS color name.
+    #[arg(long, short, default_value_t = U8Color::TRANSPARENT)]
+    pub background: U8Color,
+
+    #[command(flatten)]
+    pub gpu: GpuArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct BatchArgs {
+    /// Directory to search for PNG, BMP and QOI images, recursively.
+    pub input: PathBuf,
+
+    /// Directory to write the scaled images to, mirroring the input tree.
+    pub output: PathBuf,
+
+    /// Size of every output image. Defaults to exactly the size of each
+    /// scaled image, at a scale of 1 unless --scale is given.
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_physical_size)]
+    pub size: Option<PhysicalSize<u32>>,
+
+    #[command(flatten)]
+    pub scaling: ScalingArgs,
+
+    #[command(flatten)]
+    pub post_process: PostProcessArgs,
+
+    /// Color of the borders around the images: #rrggbb, #rrggbbaa, rgb(),
+    /// hsl() or a CSS color name.
+    #[arg(long, short, default_value_t = U8Color::TRANSPARENT)]
+    pub background: U8Color,
+
+    /// Extension, and so format, of the written images.
+    #[arg(long, default_value = "png")]
+    pub extension: String,
+
+    /// Number of threads decoding and encoding images. Defaults to the number
+    /// of available CPUs.
+    #[arg(long, short)]
+    pub jobs: Option<NonZeroUsize>,
+
+    #[command(flatten)]
+    pub gpu: GpuArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct ScalingArgs {
+    /// Scaling mode: integer, sharp-bilinear, coverage, scale2x, scale3x,
+    /// hq2x or xbrz2 to xbrz6.
+    #[arg(long, short, default_value = "integer")]
+    pub mode: ScaleMode,
+
+    /// Scale by a fixed factor instead of fitting the output.
+    #[arg(long, short, value_parser = parse_scale)]
+    pub scale: Option<f64>,
+
+    /// Width of a source pixel relative to its height, e.g. 8:7 or 0.8333.
+    #[arg(long, default_value = "1")]
+    pub pixel_aspect: PixelAspect,
+
+    /// In integer mode, reach the pixel aspect with sharp-bilinear filtering
+    /// horizontally instead of approximating it with whole factors.
+    #[arg(long)]
+    pub aspect_fallback: bool,
+
+    /// Repeat the pixel-art scaler until it reaches the integer factor.
+    #[arg(long)]
+    pub chain: bool,
+
+    /// Preset of shader passes to scale the image through, in the format of
+    /// RetroArch's slang presets (.slangp), with WGSL or slang shaders.
+    /// Replaces the passes of the scaling mode, which still places and filters
+    /// the result. In the viewer, P shows the shader parameters to tweak.
+    #[arg(long)]
+    pub preset: Option<PathBuf>,
+}
+
+impl ScalingArgs {
+    pub fn scaling(&self) -> Scaling {
+        Scaling {
+            mode: self.mode,
+            pixel_aspect: self.pixel_aspect,
+            aspect_fallback: self.aspect_fallback,
+            scale: self.scale,
+            chain: self.chain,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PostProcessArgs {
+    /// Draw the scaled image as if on a CRT.
+    #[arg(long, conflicts_with = "lcd")]
+    pub crt: bool,
+
+    /// How dark the gaps between CRT scanlines get, from 0 to 1.
+    #[arg(long, default_value = "0.5", value_parser = parse_fraction, requires = "crt")]
+    pub scanlines: f32,
+
+    /// CRT phosphor mask: none, aperture-grille, slot or shadow.
+    #[arg(long, default_value = "aperture-grille", requires = "crt")]
+    pub mask: Mask,
+
+    /// How much the CRT mask dims the phosphors it covers, from 0 to 1.
+    #[arg(long, default_value = "0.3", value_parser = parse_fraction, requires = "crt")]
+    pub mask_strength: f32,
+
+    /// Barrel distortion of the CRT screen, from 0 (flat) to 1.
+    #[arg(long, default_value = "0", value_parser = parse_fraction, requires = "crt")]
+    pub curvature: f32,
+
+    /// How much light bleeds between neighbouring pixels on the CRT, from 0
+    /// to 1.
+    #[arg(long, default_value = "0.1", value_parser = parse_fraction, requires = "crt")]
+    pub bloom: f32,
+
+    /// How much the corners of the CRT darken, from 0 to 1.
+    #[arg(long, default_value = "0", value_parser = parse_fraction, requires = "crt")]
+    pub vignette: f32,
+
+    /// Draw the scaled image as if on a handheld's LCD.
+    #[arg(long)]
+    pub lcd: bool,
+
+    /// How dark the LCD grid between pixels gets, from 0 to 1.
+    #[arg(long, default_value = "0.3", value_parser = parse_fraction, requires = "lcd")]
+    pub grid: f32,
+
+    /// How much LCD pixels split into red, green and blue, from 0 to 1.
+    #[arg(long, default_value = "0.3", value_parser = parse_fraction, requires = "lcd")]
+    pub subpixels: f32,
+
+    /// How much of the previous frame the LCD still shows after a 60 Hz
+    /// frame, from 0 to 1.
+    #[arg(long, default_value = "0.3", value_parser = parse_fraction, requires = "lcd")]
+    pub ghosting: f32,
+}
+
+impl PostProcessArgs {
+    pub fn settings(&self) -> Option<PostProcessSettings> {
+        if self.crt {
+            Some(PostProcessSettings::Crt(self.crt_settings()))
+        } else if self.lcd {
+            Some(PostProcessSettings::Lcd(self.lcd_settings()))
+        } else {
+            None
+        }
+    }
+
+    /// The CRT settings, whether or not --crt is given.
+    pub fn crt_settings(&self) -> CrtSettings {
+        CrtSettings {
+            scanlines: self.scanlines,
+            mask: self.mask,
+            mask_strength: self.mask_strength,
+            curvature: self.curvature,
+            bloom: self.bloom,
+            vignette: self.vignette,
+        }
+    }
+
+    /// The LCD settings, whether or not --lcd is given.
+    pub fn lcd_settings(&self) -> LcdSettings {
+        LcdSettings {
+            grid: self.grid,
+            subpixels: self.subpixels,
+            ghosting: self.ghosting,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CheckerboardArgs {
+    /// Draw a checkerboard behind the image, for its transparent parts to
+    /// show against.
+    #[arg(long)]
+    pub checkerboard: bool,
+
+    /// Width and height of the checkerboard squares, in --checker-unit.
+    #[arg(long, default_value = "8", value_parser = clap::value_parser!(u32).range(1..))]
+    pub checker_size: u32,
+
+    /// Unit of --checker-size: source pixels, which scale with the image, or
+    /// screen pixels.
+    #[arg(long, default_value = "screen")]
+    pub checker_unit: CheckerUnit,
+
+    /// Colors of the checkerboard squares, the top-left one first.
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["COLOR", "COLOR"],
+        default_values = ["#cccccc", "#ffffff"]
+    )]
+    pub checker_colors: Vec<U8Color>,
+}
+
+impl CheckerboardArgs {
+    /// The checkerboard settings, whether or not --checkerboard is given.
+    pub fn settings(&self) -> CheckerboardSettings {
+        CheckerboardSettings {
+            size: self.checker_size,
+            unit: self.checker_unit,
+            colors: [self.checker_colors[0], self.checker_colors[1]],
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PixelGridArgs {
+    /// Draw lines between the source pixels once the image is zoomed in far
+    /// enough, and between tiles of them with --tile-size.
+    #[arg(long)]
+    pub pixel_grid: bool,
+
+    /// Zoom factor from which the pixel grid shows.
+    #[arg(long, value_name = "FACTOR", default_value = "4", value_parser = parse_scale)]
+    pub pixel_grid_zoom: f64,
+
+    /// Color of the lines between source pixels.
+    #[arg(long, value_name = "COLOR", default_value = "#00000040")]
+    pub pixel_grid_color: U8Color,
+
+    /// Size of the tiles that get lines of their own, in source pixels, e.g.
+    /// 8x8 or 16x16.
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_physical_size)]
+    pub tile_size: Option<PhysicalSize<u32>>,
+
+    /// Color of the lines between tiles.
+    #[arg(long, value_name = "COLOR", default_value = "#ff00ffa0")]
+    pub tile_color: U8Color,
+}
+
+impl PixelGridArgs {
+    /// The pixel grid settings, whether or not --pixel-grid is given.
+    pub fn settings(&self) -> PixelGridSettings {
+        PixelGridSettings {
+            min_zoom: self.pixel_grid_zoom,
+            pixel_color: self.pixel_grid_color,
+            tile_size: self.tile_size,
+            tile_color: self.tile_color,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct GpuArgs {
+    /// Comma-separated list of graphics backends to choose from: vulkan,
+    /// metal, dx12, dx11, gl or webgpu.
+    #[arg(long, default_value = "all", value_parser = parse_backends)]
+    pub backend: wgpu::Backends,
+
+    /// Only consider software adapters.
+    #[arg(long)]
+    pub fallback_adapter: bool,
+}
+
+fn parse_size(s: &str) -> Result<LogicalSize<u32>, String> {
+    parse_physical_size(s).map(|size| LogicalSize::new(size.width, size.height))
+}
+
+fn parse_physical_size(s: &str) -> Result<PhysicalSize<u32>, String> {
+    s.split_once('x')
+        .and_then(|(width, height)| {
+            Some(PhysicalSize::new(width.parse().ok()?, height.parse().ok()?))
+        })
+        .filter(|size| size.width > 0 && size.height > 0)
+        .ok_or_else(|| format!("invalid size \"{s}\", expected WIDTHxHEIGHT"))
+}
+
+fn parse_fraction(s: &str) -> Result<f32, String> {
+    s.parse()
+        .ok()
+        .filter(|value| (0.0..=1.0).contains(value))
+        .ok_or_else(|| format!("invalid value \"{s}\", expected a number from 0 to 1"))
+}
+
+fn parse_scale(s: &str) -> Result<f64, String> {
+    s.parse()
+        .ok()
+        .filter(|value: &f64| value.is_finite() && *value > 0.0)
+        .ok_or_else(|| format!("invalid scale \"{s}\", expected a positive number"))
+}
+
+fn parse_present_mode(s: &str) -> Result<wgpu::PresentMode, String> {
+    match s {
+        "auto-vsync" => Ok(wgpu::PresentMode::AutoVsync),
+        "auto-no-vsync" => Ok(wgpu::PresentMode::AutoNoVsync),
+        "fifo" => Ok(wgpu::PresentMode::Fifo),
+        "fifo-relaxed" => Ok(wgpu::PresentMode::FifoRelaxed),
+        "immediate" => Ok(wgpu::PresentMode::Immediate),
+        "mailbox" => Ok(wgpu::PresentMode::Mailbox),
+        _ => Err(format!("unknown present mode \"{s}\"")),
+    }
+}
+
+fn parse_backends(s: &str) -> Result<wgpu::Backends, String> {
+    if s == "all" {
+        return Ok(wgpu::Backends::all());
+    }
+    match wgpu::util::parse_backends_from_comma_list(s) {
+        backends if backends.is_empty() => Err(format!("no known backend in \"{s}\"")),
+        backends => Ok(backends),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_physical_sizes() {
+        assert_eq!(
+            parse_physical_size("640x480"),
+            Ok(PhysicalSize::new(640, 480))
+        );
+        for invalid in [
+            "", "640", "640x", "x480", "0x480", "640x0", "-1x2", "640X480",
+        ] {
+            assert!(parse_physical_size(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parses_fractions() {
+        assert_eq!(parse_fraction("0"), Ok(0.0));
+        assert_eq!(parse_fraction("0.25"), Ok(0.25));
+        assert_eq!(parse_fraction("1"), Ok(1.0));
+        for invalid in ["-0.1", "1.5", "nan", "half"] {
+            assert!(parse_fraction(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parses_scales() {
+        assert_eq!(parse_scale("2.5"), Ok(2.5));
+        assert_eq!(parse_scale("1e9"), Ok(1e9));
+        for invalid in ["0", "-2", "nan", "inf", "-inf", "two"] {
+            assert!(parse_scale(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parses_backends() {
+        assert_eq!(parse_backends("all"), Ok(wgpu::Backends::all()));
+        assert_eq!(parse_backends("vulkan"), Ok(wgpu::Backends::VULKAN));
+        assert_eq!(
+            parse_backends("vulkan,gl"),
+            Ok(wgpu::Backends::VULKAN | wgpu::Backends::GL)
+        );
+        assert!(parse_backends("glide").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_scales_on_the_command_line() {
+        for scale in ["0", "nan", "inf"] {
+            let args = [
+                "perfect-scale",
+                "render",
+                "in.png",
+                "-o",
+                "out.png",
+                "--scale",
+                scale,
+            ];
+            assert!(Cli::try_parse_from(args).is_err(), "{scale}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_pixel_grid_zooms() {
+        for zoom in ["0", "-4", "nan", "inf"] {
+            let args = ["perfect-scale", "in.png", "--pixel-grid-zoom", zoom];
+            assert!(Cli::try_parse_from(args).is_err(), "{zoom}");
+        }
+        let args = ["perfect-scale", "in.png", "--pixel-grid-zoom", "2.5"];
+        let view = Cli::try_parse_from(args).unwrap().view.unwrap();
+        assert_eq!(view.pixel_grid.pixel_grid_zoom, 2.5);
+    }
+
+    #[test]
+    fn rejects_empty_checker_squares() {
+        let args = ["perfect-scale", "in.png", "--checker-size", "0"];
+        assert!(Cli::try_parse_from(args).is_err());
+        let args = ["perfect-scale", "in.png", "--checker-size", "1"];
+        let view = Cli::try_parse_from(args).unwrap().view.unwrap();
+        assert_eq!(view.checkerboard.checker_size, 1);
+    }
+
+    #[test]
+    fn parses_viewer_arguments_next_to_the_subcommands() {
+        let cli = Cli::try_parse_from(["perfect-scale", "in.png", "--snap"]).unwrap();
+        assert!(cli.command.is_none());
+        let view = cli.view.expect("viewer arguments");
+        assert_eq!(view.input, PathBuf::from("in.png"));
+        assert!(view.snap);
+
+        let cli =
+            Cli::try_parse_from(["perfect-scale", "render", "in.png", "-o", "out.png"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Render(_))));
+    }
+}
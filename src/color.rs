@@ -0,0 +1,550 @@
+use std::{fmt, str::FromStr};
+
+/// An sRGB-encoded color with straight alpha, 8 bits per channel, the way
+/// images and the command line give colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct U8Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+/// A color with linear channels and straight alpha, the way shaders blend
+/// colors and render targets take them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearColor {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Invalid color \"{0}\", expected #rgb, #rrggbb or #rrggbbaa, rgb(), rgba(), hsl(), hsla() or a CSS color name"
+)]
+pub struct ParseColorError(String);
+
+pub const CORNFLOWER_BLUE: U8Color = U8Color::from_rgba(100, 149, 237, 255);
+
+impl U8Color {
+    pub const TRANSPARENT: Self = Self::from_rgba(0, 0, 0, 0);
+
+    pub const fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// The color with its channels decoded with the exact sRGB transfer
+    /// function.
+    pub fn to_linear(self) -> LinearColor {
+        LinearColor {
+            r: srgb_to_linear(self.r as f64 / 255.0),
+            g: srgb_to_linear(self.g as f64 / 255.0),
+            b: srgb_to_linear(self.b as f64 / 255.0),
+            a: self.a as f64 / 255.0,
+        }
+    }
+}
+
+impl LinearColor {
+    /// The color with its channels multiplied by its alpha, the way blending
+    /// over what a target already holds takes them.
+    pub fn premultiplied(self) -> Self {
+        Self {
+            r: self.r * self.a,
+            g: self.g * self.a,
+            b: self.b * self.a,
+            a: self.a,
+        }
+    }
+
+    /// The color with its channels divided by its alpha again, or transparent
+    /// black if it has none.
+    pub fn unpremultiplied(self) -> Self {
+        if self.a <= 0.0 {
+            return Self {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 0.0,
+            };
+        }
+        Self {
+            r: self.r / self.a,
+            g: self.g / self.a,
+            b: self.b / self.a,
+            a: self.a,
+        }
+    }
+
+    /// The color with its channels encoded with the exact sRGB transfer
+    /// function, in 8 bits, clamping ones outside of 0 to 1.
+    pub fn to_srgb(self) -> U8Color {
+        let channel = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        U8Color::from_rgba(
+            channel(linear_to_srgb(self.r)),
+            channel(linear_to_srgb(self.g)),
+            channel(linear_to_srgb(self.b)),
+            channel(self.a),
+        )
+    }
+
+    /// The channels in the order and precision that shaders take colors in.
+    pub fn to_array(self) -> [f32; 4] {
+        [self.r as f32, self.g as f32, self.b as f32, self.a as f32]
+    }
+
+    /// The color as every render target takes it: the GPU encodes it again
+    /// when writing to sRGB targets.
+    pub fn to_wgpu(self) -> wgpu::Color {
+        wgpu::Color {
+            r: self.r,
+            g: self.g,
+            b: self.b,
+            a: self.a,
+        }
+    }
+}
+
+fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl FromStr for U8Color {
+    type Err = ParseColorError;
+
+    /// Parses the CSS color syntaxes that name sRGB colors: hex colors,
+    /// `rgb()` and `hsl()` with commas or spaces, with or without alpha, and
+    /// the named colors, all in any case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseColorError(s.to_owned());
+        let lowercase = s.trim().to_ascii_lowercase();
+        if let Some(hex) = lowercase.strip_prefix('#') {
+            return parse_hex(hex).ok_or_else(error);
+        }
+        if let Some((function, arguments)) = lowercase
+            .strip_suffix(')')
+            .and_then(|rest| rest.split_once('('))
+        {
+            let color = match function.trim_end() {
+                "rgb" | "rgba" => parse_rgb(arguments),
+                "hsl" | "hsla" => parse_hsl(arguments),
+                _ => None,
+            };
+            return color.ok_or_else(error);
+        }
+        if lowercase == "transparent" {
+            return Ok(Self::TRANSPARENT);
+        }
+        NAMED_COLORS
+            .iter()
+            .find(|(name, _)| *name == lowercase)
+            .map(|&(_, rgb)| {
+                let [_, r, g, b] = rgb.to_be_bytes();
+                Self::from_rgba(r, g, b, 255)
+            })
+            .ok_or_else(error)
+    }
+}
+
+impl fmt::Display for U8Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)?;
+        if self.a != 255 {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+/// `rgb`, `rgba`, `rrggbb` or `rrggbbaa` hex digits.
+fn parse_hex(hex: &str) -> Option<U8Color> {
+    // from_str_radix would take a sign in front of a pair of digits.
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|digit| [digit, digit]).collect(),
+        6 | 8 => hex.to_owned(),
+        _ => return None,
+    };
+    let channel = |i: usize| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok();
+    let a = if digits.len() == 8 { channel(6)? } else { 255 };
+    Some(U8Color::from_rgba(channel(0)?, channel(2)?, channel(4)?, a))
+}
+
+/// Red, green and blue from 0 to 255 or as percentages, then the optional
+/// alpha.
+fn parse_rgb(arguments: &str) -> Option<U8Color> {
+    let (channels, alpha) = split_arguments(arguments)?;
+    let mut rgb = [0; 3];
+    for (channel, argument) in rgb.iter_mut().zip(channels) {
+        let value = match argument.strip_suffix('%') {
+            Some(percentage) => percentage.parse::<f64>().ok()? / 100.0,
+            None => argument.parse::<f64>().ok()? / 255.0,
+        };
+        *channel = to_u8(value)?;
+    }
+    let [r, g, b] = rgb;
+    Some(U8Color::from_rgba(r, g, b, alpha))
+}
+
+/// Hue in degrees, saturation and lightness as percentages, then the
+/// optional alpha.
+fn parse_hsl(arguments: &str) -> Option<U8Color> {
+    let (arguments, alpha) = split_arguments(arguments)?;
+    let [hue, saturation, lightness] = arguments;
+    let hue: f64 = hue.strip_suffix("deg").unwrap_or(hue).parse().ok()?;
+    let percentage = |argument: &str| {
+        let value: f64 = argument
+            .strip_suffix('%')
+            .unwrap_or(argument)
+            .parse()
+            .ok()?;
+        Some((value / 100.0).clamp(0.0, 1.0))
+    };
+    let (saturation, lightness) = (percentage(saturation)?, percentage(lightness)?);
+
+    // The hue picks a point on the edges of the RGB cube, which saturation
+    // pulls towards the gray of the given lightness.
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let channel = |offset: f64| {
+        let k = (offset + hue.rem_euclid(360.0) / 30.0) % 12.0;
+        lightness - chroma / 2.0 * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    Some(U8Color::from_rgba(
+        to_u8(channel(0.0))?,
+        to_u8(channel(8.0))?,
+        to_u8(channel(4.0))?,
+        alpha,
+    ))
+}
+
+/// The three arguments of a color function and its alpha, opaque unless
+/// given. Arguments are separated by commas, or by spaces with a slash
+/// before the alpha.
+fn split_arguments(arguments: &str) -> Option<([&str; 3], u8)> {
+    let mut arguments: Vec<&str> = if arguments.contains(',') {
+        arguments.split(',').map(str::trim).collect()
+    } else {
+        let (channels, alpha) = match arguments.split_once('/') {
+            Some((channels, alpha)) => (channels, Some(alpha.trim())),
+            None => (arguments, None),
+        };
+        let mut arguments: Vec<&str> = channels.split_whitespace().collect();
+        arguments.extend(alpha);
+        arguments
+    };
+    let alpha = match arguments.len() {
+        3 => 255,
+        4 => {
+            let alpha = arguments.pop()?;
+            match alpha.strip_suffix('%') {
+                Some(percentage) => to_u8(percentage.parse::<f64>().ok()? / 100.0)?,
+                None => to_u8(alpha.parse().ok()?)?,
+            }
+        }
+        _ => return None,
+    };
+    Some((arguments.try_into().ok()?, alpha))
+}
+
+/// A channel from 0 to 1 in 8 bits, clamping ones outside of that range.
+fn to_u8(value: f64) -> Option<u8> {
+    value
+        .is_finite()
+        .then(|| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// The CSS named colors, as 0xrrggbb.
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> U8Color {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(parse("#f80"), U8Color::from_rgba(255, 136, 0, 255));
+        assert_eq!(parse("#f808"), U8Color::from_rgba(255, 136, 0, 136));
+        assert_eq!(parse("#6495ED"), CORNFLOWER_BLUE);
+        assert_eq!(parse(" #12345678 "), U8Color::from_rgba(18, 52, 86, 120));
+    }
+
+    #[test]
+    fn parses_rgb_functions() {
+        assert_eq!(
+            parse("rgb(255, 0, 128)"),
+            U8Color::from_rgba(255, 0, 128, 255)
+        );
+        assert_eq!(
+            parse("RGBA(255,0,128,0.5)"),
+            U8Color::from_rgba(255, 0, 128, 128)
+        );
+        assert_eq!(
+            parse("rgb(100% 0% 50% / 25%)"),
+            U8Color::from_rgba(255, 0, 128, 64)
+        );
+        // Channels outside of the range clamp, as in CSS.
+        assert_eq!(
+            parse("rgb(300, -20, 0)"),
+            U8Color::from_rgba(255, 0, 0, 255)
+        );
+    }
+
+    #[test]
+    fn parses_hsl_functions() {
+        assert_eq!(
+            parse("hsl(0, 100%, 50%)"),
+            U8Color::from_rgba(255, 0, 0, 255)
+        );
+        assert_eq!(
+            parse("hsl(120deg 100% 25%)"),
+            U8Color::from_rgba(0, 128, 0, 255)
+        );
+        assert_eq!(
+            parse("hsla(240, 100%, 50%, 0.5)"),
+            U8Color::from_rgba(0, 0, 255, 128)
+        );
+        assert_eq!(
+            parse("hsl(-120, 100%, 50%)"),
+            U8Color::from_rgba(0, 0, 255, 255)
+        );
+        assert_eq!(
+            parse("hsl(0, 0%, 100%)"),
+            U8Color::from_rgba(255, 255, 255, 255)
+        );
+    }
+
+    #[test]
+    fn parses_named_colors() {
+        assert_eq!(parse("CornflowerBlue"), CORNFLOWER_BLUE);
+        assert_eq!(
+            parse("rebeccapurple"),
+            U8Color::from_rgba(102, 51, 153, 255)
+        );
+        assert_eq!(parse("transparent"), U8Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn rejects_invalid_colors() {
+        for color in [
+            "",
+            "#",
+            "#12",
+            "#12345",
+            "#gggggg",
+            "#ééé",
+            "#+f+f+f",
+            "#-1-1-1-1",
+            "rgb(1, 2)",
+            "rgb(1, 2, 3, 4, 5)",
+            "rgb(1, 2, x)",
+            "rgb(1, 2, nan)",
+            "rgb(1, 2, 3",
+            "cmyk(0, 0, 0, 0)",
+            "hsl(red, 100%, 50%)",
+            "notacolor",
+        ] {
+            let error = color.parse::<U8Color>().unwrap_err();
+            assert_eq!(error.0, color, "{color}");
+        }
+    }
+
+    #[test]
+    fn displays_colors_as_hex() {
+        assert_eq!(CORNFLOWER_BLUE.to_string(), "#6495ed");
+        assert_eq!(U8Color::from_rgba(1, 2, 3, 4).to_string(), "#01020304");
+    }
+
+    #[test]
+    fn converts_between_srgb_and_linear() {
+        let linear = U8Color::from_rgba(128, 0, 255, 128).to_linear();
+        assert!((linear.r - 0.2158605).abs() < 1e-6);
+        assert_eq!((linear.g, linear.b), (0.0, 1.0));
+        assert!((linear.a - 128.0 / 255.0).abs() < 1e-12);
+        for value in 0..=255 {
+            let color = U8Color::from_rgba(value, value, value, value);
+            assert_eq!(color.to_linear().to_srgb(), color);
+        }
+    }
+
+    #[test]
+    fn undoes_premultiplying() {
+        let color = U8Color::from_rgba(255, 136, 0, 64).to_linear();
+        let round_trip = color.premultiplied().unpremultiplied();
+        assert!((round_trip.g - color.g).abs() < 1e-12);
+        assert_eq!(round_trip.to_srgb(), U8Color::from_rgba(255, 136, 0, 64));
+        let transparent = U8Color::from_rgba(255, 255, 255, 0).to_linear();
+        assert_eq!(
+            transparent.unpremultiplied().to_srgb(),
+            U8Color::TRANSPARENT
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{
+    blit::{self, Blit, ExtraBindings},
+    renderer::{self, Renderer},
+    scaling::Viewport,
+    texture::Texture,
+};
+use std::str::FromStr;
+use winit::dpi::PhysicalSize;
+
+/// Phosphor pattern drawn over the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mask {
+    None,
+    /// Vertical stripes of red, green and blue, as on a Trinitron.
+    #[default]
+    ApertureGrille,
+    /// Stripes broken into slots, staggered between neighbouring triads.
+    Slot,
+    /// Triads of dots, staggered between rows.
+    Shadow,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown CRT mask \"{0}\", expected one of: none, aperture-grille, slot, shadow")]
+pub struct ParseMaskError(String);
+
+impl FromStr for Mask {
+    type Err = ParseMaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "aperture-grille" => Ok(Self::ApertureGrille),
+            "slot" => Ok(Self::Slot),
+            "shadow" => Ok(Self::Shadow),
+            _ => Err(ParseMaskError(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrtSettings {
+    /// How dark the gaps between scanlines get, from 0 to 1. Every source row
+    /// is a scanline, so they need a scale of at least 2 to show.
+    pub scanlines: f32,
+    pub mask: Mask,
+    /// How much the mask dims the phosphors it covers, from 0 to 1.
+    pub mask_strength: f32,
+    /// Barrel distortion of the screen, 0 for a flat one.
+    pub curvature: f32,
+    /// How much light bleeds from each source pixel into its neighbours.
+    pub bloom: f32,
+    /// How much the corners darken, from 0 to 1.
+    pub vignette: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct CrtUniforms {
+    image: [f32; 4],
+    background: [f32; 4],
+    source_size: [f32; 2],
+    scanlines: f32,
+    mask: u32,
+    mask_strength: f32,
+    curvature: f32,
+    bloom: f32,
+    vignette: f32,
+}
+
+/// Post-process stage that draws a scaled image as if on a CRT.
+///
+/// The image is rendered into [`Crt::input`], at the size of the final target,
+/// and this stage draws it from there onto the target.
+pub struct Crt {
+    settings: CrtSettings,
+    pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    uniform_buffer: wgpu::Buffer,
+    input: Texture,
+    blit: Blit,
+}
+
+impl Crt {
+    pub fn new(
+        device: &wgpu::Device,
+        renderer: &Renderer,
+        target_format: wgpu::TextureFormat,
+        settings: CrtSettings,
+    ) -> Self {
+        let blit_pipeline = renderer.blit_pipeline();
+        let pipeline = blit_pipeline.create_pipeline(
+            device,
+            target_format,
+            "fs_crt",
+            ExtraBindings::Parameters,
+        );
+        let sampler = blit::create_sampler(device, wgpu::FilterMode::Linear);
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("CRT uniform buffer"),
+            size: std::mem::size_of::<CrtUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let input = Texture::render_target(
+            device,
+            PhysicalSize::new(1, 1),
+            target_format,
+            Some("CRT input texture"),
+        );
+        let blit = Blit::new(device, blit_pipeline, &input.view, &sampler).with_parameters(
+            device,
+            blit_pipeline,
+            &uniform_buffer,
+        );
+
+        Self {
+            settings,
+            pipeline,
+            sampler,
+            uniform_buffer,
+            input,
+            blit,
+        }
+    }
+
+    /// Where to render the scaled image before [`Crt::render`].
+    pub fn input(&self) -> &wgpu::TextureView {
+        &self.input.view
+    }
+
+    /// Sizes the input to the target, and places the image the way `renderer`
+    /// scaled it into the target.
+    pub fn set_layout(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        renderer: &Renderer,
+        viewport: Viewport,
+        target_size: PhysicalSize<u32>,
+        background: wgpu::Color,
+    ) {
+        let blit_pipeline = renderer.blit_pipeline();
+        if self.input.size() != target_size {
+            self.input = Texture::render_target(
+                device,
+                target_size,
+                self.input.texture.format(),
+                Some("CRT input texture"),
+            );
+            self.blit = Blit::new(device, blit_pipeline, &self.input.view, &self.sampler)
+                .with_parameters(device, blit_pipeline, &self.uniform_buffer);
+        }
+        self.blit
+            .set_viewport(queue, Viewport::covering(target_size), target_size);
+
+        let source_size = renderer.source().size();
+        let settings = &self.settings;
+        let uniforms = CrtUniforms {
+            image: [
+                viewport.x as f32,
+                viewport.y as f32,
+                viewport.width as f32,
+                viewport.height as f32,
+            ],
+            background: [
+                background.r as f32,
+                background.g as f32,
+                background.b as f32,
+                background.a as f32,
+            ],
+            source_size: [source_size.width as f32, source_size.height as f32],
+            scanlines: settings.scanlines,
+            mask: settings.mask as u32,
+            mask_strength: settings.mask_strength,
+            curvature: settings.curvature,
+            bloom: settings.bloom,
+            vignette: settings.vignette,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+    }
+
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let mut render_pass =
+            renderer::begin_pass(encoder, view, wgpu::Color::TRANSPARENT, "CRT pass");
+        self.blit.draw(&mut render_pass, &self.pipeline);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::scaling::Scaling;
+use std::str::FromStr;
+use winit::{
+    dpi::PhysicalSize,
+    monitor::{MonitorHandle, VideoMode},
+    window::Fullscreen,
+};
+
+/// How the viewer covers the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FullscreenMode {
+    /// A borderless window the size of the monitor, in its current video mode.
+    Borderless,
+    /// The monitor to itself, switched to the video mode that fits the image
+    /// best.
+    Exclusive,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown fullscreen mode \"{0}\", expected borderless or exclusive")]
+pub struct ParseFullscreenModeError(String);
+
+impl FromStr for FullscreenMode {
+    type Err = ParseFullscreenModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "borderless" => Ok(Self::Borderless),
+            "exclusive" => Ok(Self::Exclusive),
+            _ => Err(ParseFullscreenModeError(s.to_owned())),
+        }
+    }
+}
+
+impl FullscreenMode {
+    /// Whether `fullscreen` is in this mode.
+    pub fn matches(self, fullscreen: &Fullscreen) -> bool {
+        match fullscreen {
+            Fullscreen::Borderless(_) => self == Self::Borderless,
+            Fullscreen::Exclusive(_) => self == Self::Exclusive,
+        }
+    }
+
+    /// Fullscreen on `monitor`, or the window's current one if `None`.
+    ///
+    /// Exclusive fullscreen switches to the largest video mode that the image
+    /// fills at a whole factor when fitted by `scaling`, so that every source
+    /// pixel covers the same number of screen pixels without any border. Falls
+    /// back to the largest video mode, and to borderless fullscreen if the
+    /// monitor is unknown.
+    pub fn fullscreen(
+        self,
+        monitor: Option<MonitorHandle>,
+        scaling: &Scaling,
+        source: PhysicalSize<u32>,
+    ) -> Fullscreen {
+        let monitor = match (self, monitor) {
+            (Self::Borderless, monitor) => return Fullscreen::Borderless(monitor),
+            (Self::Exclusive, Some(monitor)) => monitor,
+            (Self::Exclusive, None) => {
+                log::warn!("Unknown monitor, using borderless fullscreen");
+                return Fullscreen::Borderless(None);
+            }
+        };
+        let video_mode = integer_video_mode(monitor.video_modes(), scaling, source).or_else(|| {
+            log::warn!(
+                "No video mode is a whole multiple of {}x{}, using the largest one",
+                source.width,
+                source.height
+            );
+            largest(monitor.video_modes(), |_| true)
+        });
+        match video_mode {
+            Some(video_mode) => {
+                log::info!("Switching to {video_mode}");
+                Fullscreen::Exclusive(video_mode)
+            }
+            None => {
+                log::warn!("The monitor has no video modes, using borderless fullscreen");
+                Fullscreen::Borderless(Some(monitor))
+            }
+        }
+    }
+}
+
+/// What video modes are picked by.
+trait VideoModeProperties {
+    fn size(&self) -> PhysicalSize<u32>;
+    fn refresh_rate_millihertz(&self) -> u32;
+    fn bit_depth(&self) -> u16;
+}
+
+impl VideoModeProperties for VideoMode {
+    fn size(&self) -> PhysicalSize<u32> {
+        self.size()
+    }
+
+    fn refresh_rate_millihertz(&self) -> u32 {
+        self.refresh_rate_millihertz()
+    }
+
+    fn bit_depth(&self) -> u16 {
+        self.bit_depth()
+    }
+}
+
+/// The largest of `video_modes` whose height is a whole multiple of the
+/// source's, and that the fitted image fills entirely.
+fn integer_video_mode<M: VideoModeProperties>(
+    video_modes: impl Iterator<Item = M>,
+    scaling: &Scaling,
+    source: PhysicalSize<u32>,
+) -> Option<M> {
+    let scaling = Scaling {
+        scale: None,
+        ..*scaling
+    };
+    largest(video_modes, |size| {
+        size.height % source.height == 0 && scaling.layout(source, size).viewport.size() == size
+    })
+}
+
+/// The largest of `video_modes` whose size passes `filter`, with the highest
+/// refresh rate and bit depth among those of that size.
+fn largest<M: VideoModeProperties>(
+    video_modes: impl Iterator<Item = M>,
+    filter: impl Fn(PhysicalSize<u32>) -> bool,
+) -> Option<M> {
+    video_modes
+        .filter(|video_mode| filter(video_mode.size()))
+        .max_by_key(|video_mode| {
+            let size = video_mode.size();
+            (
+                size.width as u64 * size.height as u64,
+                video_mode.refresh_rate_millihertz(),
+                video_mode.bit_depth(),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scaling::PixelAspect;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Mode(u32, u32, u32, u16);
+
+    impl VideoModeProperties for Mode {
+        fn size(&self) -> PhysicalSize<u32> {
+            PhysicalSize::new(self.0, self.1)
+        }
+
+        fn refresh_rate_millihertz(&self) -> u32 {
+            self.2
+        }
+
+        fn bit_depth(&self) -> u16 {
+            self.3
+        }
+    }
+
+    const SOURCE: PhysicalSize<u32> = PhysicalSize::new(320, 240);
+    const MODES: [Mode; 6] = [
+        Mode(1920, 1080, 60_000, 32),
+        Mode(1280, 960, 60_000, 32),
+        Mode(1600, 1200, 60_000, 32),
+        Mode(1600, 1200, 75_000, 16),
+        Mode(1600, 1200, 75_000, 32),
+        Mode(640, 480, 120_000, 32),
+    ];
+
+    #[test]
+    fn picks_the_largest_mode_the_image_fills_at_a_whole_factor() {
+        // Ties go to the highest refresh rate, then bit depth.
+        let scaling = Scaling::default();
+        assert_eq!(
+            integer_video_mode(MODES.into_iter(), &scaling, SOURCE),
+            Some(Mode(1600, 1200, 75_000, 32))
+        );
+        // The image leaves a border in 1600x1200 at 8:7, but fills 1280x960.
+        let scaling = Scaling {
+            pixel_aspect: PixelAspect(8.0 / 7.0),
+            ..Scaling::default()
+        };
+        assert_eq!(
+            integer_video_mode(MODES.into_iter(), &scaling, SOURCE),
+            Some(Mode(1280, 960, 60_000, 32))
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_largest_mode_when_the_image_fills_none() {
+        let scaling = Scaling::default();
+        let modes = [Mode(1920, 1080, 60_000, 32), Mode(1024, 768, 60_000, 32)];
+        assert_eq!(
+            integer_video_mode(modes.into_iter(), &scaling, SOURCE),
+            None
+        );
+        assert_eq!(
+            largest(modes.into_iter(), |_| true),
+            Some(Mode(1920, 1080, 60_000, 32))
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use std::error::Error;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GpuError {
+    #[error("No compatible adapter found")]
+    NoAdapterFound,
+}
+
+pub fn create_instance(backends: wgpu::Backends) -> wgpu::Instance {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends,
+        dx12_shader_compiler: Default::default(),
+    })
+}
+
+/// Picks an adapter and opens a device on it. Without a surface this works on
+/// machines with no display at all, e.g. with llvmpipe or lavapipe.
+pub async fn request_device(
+    instance: &wgpu::Instance,
+    compatible_surface: Option<&wgpu::Surface>,
+    force_fallback_adapter: bool,
+) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), Box<dyn Error>> {
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptionsBase {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface,
+            force_fallback_adapter,
+        })
+        .await
+        .ok_or(GpuError::NoAdapterFound)?;
+    log::info!("Using adapter {:?}", adapter.get_info());
+    // Shader chains fall back to clamping to the edge without it.
+    let features = adapter.features() & wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER;
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                features,
+                limits: wgpu::Limits::default(),
+                label: None,
+            },
+            None,
+        )
+        .await?;
+    Ok((adapter, device, queue))
+}
@@ -0,0 +1,148 @@
+use crate::{
+    cli::{GpuArgs, RenderArgs},
+    color::U8Color,
+    gpu,
+    post_process::{PostProcess, PostProcessSettings},
+    renderer::Renderer,
+    scaling::Scaling,
+    srgb,
+    texture::{self, Texture},
+};
+use std::{error::Error, path::Path};
+use winit::dpi::PhysicalSize;
+
+const OUTPUT_FORMAT: wgpu::TextureFormat = srgb::IMAGE_FORMAT;
+
+/// Scales images into offscreen textures, reusing one device and set of
+/// pipelines for every image.
+pub struct OffscreenRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    /// Starts out with a placeholder source, replaced by every image.
+    renderer: Renderer,
+    post_process_settings: Option<PostProcessSettings>,
+    post_process: Option<PostProcess>,
+}
+
+impl OffscreenRenderer {
+    /// Images are scaled through the shader chain of `preset` if there is
+    /// one, and go through a post-process stage after scaling if
+    /// `post_process_settings` has one.
+    pub async fn new(
+        args: &GpuArgs,
+        preset: Option<&Path>,
+        post_process_settings: Option<PostProcessSettings>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let instance = gpu::create_instance(args.backend);
+        let (_, device, queue) =
+            gpu::request_device(&instance, None, args.fallback_adapter).await?;
+        let placeholder = Texture::from_image(
+            &device,
+            &queue,
+            &image::RgbaImage::new(1, 1),
+            Some("Source texture"),
+        );
+        let mut renderer = Renderer::new(&device, OUTPUT_FORMAT, placeholder);
+        if let Some(preset) = preset {
+            renderer.load_shader_chain(&device, &queue, preset)?;
+        }
+        Ok(Self {
+            device,
+            queue,
+            renderer,
+            post_process_settings,
+            post_process: None,
+        })
+    }
+
+    /// Scales `image` into an image of `size`, or of exactly the scaled size
+    /// (at a scale of 1 unless `scaling` fixes one) if there is none.
+    pub fn render(
+        &mut self,
+        image: &image::RgbaImage,
+        mut scaling: Scaling,
+        size: Option<PhysicalSize<u32>>,
+        background: U8Color,
+    ) -> Result<image::RgbaImage, Box<dyn Error>> {
+        let (width, height) = image.dimensions();
+        let source_size = PhysicalSize::new(width, height);
+        texture::check_size(&self.device, source_size)?;
+        let size = match size {
+            Some(size) => size,
+            None => {
+                scaling.scale.get_or_insert(1.0);
+                scaling.layout(source_size, source_size).viewport.size()
+            }
+        };
+        texture::check_size(&self.device, size)?;
+        let layout = scaling.layout(source_size, size);
+        for (_, pass_size) in layout.passes(source_size) {
+            texture::check_size(&self.device, pass_size)?;
+        }
+
+        // Colors stay premultiplied up to the readback, so the borders are too.
+        let background = background.to_linear().premultiplied().to_wgpu();
+        let source = Texture::from_image(&self.device, &self.queue, image, Some("Source texture"));
+        let renderer = &mut self.renderer;
+        renderer.set_source(&self.device, source);
+        let viewport = layout.viewport;
+        renderer.set_layout(&self.device, &self.queue, layout, size);
+        let post_process = self.post_process_settings.map(|settings| {
+            let post_process = self.post_process.get_or_insert_with(|| {
+                PostProcess::new(&self.device, renderer, OUTPUT_FORMAT, settings)
+            });
+            // Every image is a still of its own.
+            post_process.forget_history();
+            post_process.set_layout(
+                &self.device,
+                &self.queue,
+                renderer,
+                viewport,
+                size,
+                background,
+            );
+            post_process
+        });
+
+        let output =
+            Texture::render_target(&self.device, size, OUTPUT_FORMAT, Some("Output texture"));
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render encoder"),
+            });
+        match post_process {
+            Some(post_process) => {
+                renderer.render(&self.queue, &mut encoder, post_process.input(), background);
+                post_process.render(&self.queue, &mut encoder, &output.view);
+            }
+            None => renderer.render(&self.queue, &mut encoder, &output.view, background),
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let pixels = texture::read_texture(&self.device, &self.queue, &output)?;
+        Ok(srgb::encode_image(size, &pixels))
+    }
+}
+
+/// Renders the scaled input into an offscreen texture and saves it, without
+/// touching the windowing system.
+pub async fn render(args: RenderArgs) -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+    let image = texture::load_image(&args.input)?;
+    let mut renderer = OffscreenRenderer::new(
+        &args.gpu,
+        args.scaling.preset.as_deref(),
+        args.post_process.settings(),
+    )
+    .await?;
+    let output = renderer.render(&image, args.scaling.scaling(), args.size, args.background)?;
+    output.save(&args.output)?;
+    log::info!(
+        "Wrote {}x{} image to {}",
+        output.width(),
+        output.height(),
+        args.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,199 @@
+//! Lookup table for the hq2x scaler.
+//!
+//! `fs_hqx` classifies each source pixel by which of its eight neighbours
+//! differ from it and by which pairs of edge neighbours differ from each
+//! other, then reads how to blend every output pixel from a table built here.
+//!
+//! hq2x's rules are written for the top-left output pixel and mirrored for
+//! the others.
+
+/// Neighbour indices, with the source pixel at 4:
+///
+/// ```text
+/// 0 1 2
+/// 3 4 5
+/// 6 7 8
+/// ```
+const FLIP_X: [usize; 9] = [2, 1, 0, 5, 4, 3, 8, 7, 6];
+const FLIP_Y: [usize; 9] = [6, 7, 8, 3, 4, 5, 0, 1, 2];
+
+/// Edge neighbour pairs compared for the second half of the classification,
+/// in bit order.
+const CROSS_PAIRS: [(usize, usize); 4] = [(1, 5), (5, 7), (7, 3), (3, 1)];
+
+/// Number of differing-neighbour patterns, and of differing-pair patterns.
+const PATTERNS: u32 = 256;
+const CROSSES: u32 = 16;
+
+/// Table for hq2x.
+///
+/// Each pattern and cross classification gets a 2×2 tile, at
+/// `(pattern * 2, cross * 2)`, with a texel for each output pixel. It holds
+/// the weights of the source pixel and of the horizontal, vertical and
+/// diagonal neighbours on the side of that output pixel.
+pub fn lookup_table() -> image::RgbaImage {
+    image::RgbaImage::from_fn(PATTERNS * 2, CROSSES * 2, |x, y| {
+        let quadrant = Quadrant::new((x / 2) as u8, (y / 2) as u8, x % 2 == 1, y % 2 == 1);
+        image::Rgba(quadrant.hq2x().map(|weight| (weight * 255.0).round() as u8))
+    })
+}
+
+/// Classification of a source pixel seen from one of its output pixels,
+/// mirrored so that it is the top-left one.
+struct Quadrant {
+    pattern: u8,
+    cross: u8,
+    neighbours: [usize; 9],
+}
+
+impl Quadrant {
+    fn new(pattern: u8, cross: u8, flip_x: bool, flip_y: bool) -> Self {
+        let mut neighbours = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+        if flip_x {
+            neighbours = neighbours.map(|n| FLIP_X[n]);
+        }
+        if flip_y {
+            neighbours = neighbours.map(|n| FLIP_Y[n]);
+        }
+
+        let mut local = 0;
+        for (bit, &n) in neighbours.iter().filter(|&&n| n != 4).enumerate() {
+            local |= ((pattern >> pattern_bit(n)) & 1) << bit;
+        }
+
+        Self {
+            pattern: local,
+            cross,
+            neighbours,
+        }
+    }
+
+    /// Whether the masked neighbours differing from the source pixel are
+    /// exactly those in `value`.
+    fn matches(&self, mask: u8, value: u8) -> bool {
+        self.pattern & mask == value
+    }
+
+    /// Whether two edge neighbours differ from each other.
+    fn differs(&self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.neighbours[a], self.neighbours[b]);
+        let bit = CROSS_PAIRS
+            .iter()
+            .position(|&pair| pair == (a, b) || pair == (b, a))
+            .expect("mirroring keeps edge neighbours adjacent");
+        self.cross & (1 << bit) != 0
+    }
+
+    /// Weights of the source pixel and its horizontal, vertical and diagonal
+    /// neighbours for the top-left output pixel.
+    fn hq2x(&self) -> [f32; 4] {
+        let p = |mask, value| self.matches(mask, value);
+        let [e, h, v, c] = if (p(0xbf, 0x37) || p(0xdb, 0x13)) && self.differs(1, 5) {
+            [3, 1, 0, 0]
+        } else if (p(0xdb, 0x49) || p(0xef, 0x6d)) && self.differs(7, 3) {
+            [3, 0, 1, 0]
+        } else if (p(0x0b, 0x0b) || p(0xfe, 0x4a) || p(0xfe, 0x1a)) && self.differs(3, 1) {
+            [1, 0, 0, 0]
+        } else if (p(0x6f, 0x2a)
+            || p(0x5b, 0x0a)
+            || p(0xbf, 0x3a)
+            || p(0xdf, 0x5a)
+            || p(0x9f, 0x8a)
+            || p(0xcf, 0x8a)
+            || p(0xef, 0x4e)
+            || p(0x3f, 0x0e)
+            || p(0xfb, 0x5a)
+            || p(0xbb, 0x8a)
+            || p(0x7f, 0x5a)
+            || p(0xaf, 0x8a)
+            || p(0xeb, 0x8a))
+            && self.differs(3, 1)
+        {
+            [3, 0, 0, 1]
+        } else if p(0x0b, 0x08) {
+            [2, 0, 1, 1]
+        } else if p(0x0b, 0x02) {
+            [2, 1, 0, 1]
+        } else if p(0x2f, 0x2f) {
+            [14, 1, 1, 0]
+        } else if p(0xbf, 0x37) || p(0xdb, 0x13) {
+            [5, 1, 2, 0]
+        } else if p(0xdb, 0x49) || p(0xef, 0x6d) {
+            [5, 2, 1, 0]
+        } else if p(0x1b, 0x03) || p(0x4f, 0x43) || p(0x8b, 0x83) || p(0x6b, 0x43) {
+            [3, 1, 0, 0]
+        } else if p(0x4b, 0x09) || p(0x8b, 0x89) || p(0x1f, 0x19) || p(0x3b, 0x19) {
+            [3, 0, 1, 0]
+        } else if p(0x7e, 0x2a) || p(0xef, 0xab) || p(0xbf, 0x8f) || p(0x7e, 0x0e) {
+            [2, 3, 3, 0]
+        } else if p(0xfb, 0x6a)
+            || p(0x6f, 0x6e)
+            || p(0x3f, 0x3e)
+            || p(0xfb, 0xfa)
+            || p(0xdf, 0xde)
+            || p(0xdf, 0x1e)
+        {
+            [3, 0, 0, 1]
+        } else if p(0x0a, 0x00)
+            || p(0x4f, 0x4b)
+            || p(0x9f, 0x1b)
+            || p(0x2f, 0x0b)
+            || p(0xbe, 0x0a)
+            || p(0xee, 0x0a)
+            || p(0x7e, 0x0a)
+            || p(0xeb, 0x4b)
+            || p(0x3b, 0x1b)
+        {
+            [2, 1, 1, 0]
+        } else {
+            [6, 1, 1, 0]
+        };
+        let total = (e + h + v + c) as f32;
+        [e, h, v, c].map(|weight| weight as f32 / total)
+    }
+}
+
+/// Bit of a neighbour in the differing-neighbour pattern, which skips the
+/// source pixel itself.
+fn pattern_bit(neighbour: usize) -> usize {
+    if neighbour < 4 {
+        neighbour
+    } else {
+        neighbour - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Weights of the source pixel and its horizontal, vertical and diagonal
+    /// neighbours for the four output pixels of a classification.
+    fn tile(pattern: u32, cross: u32) -> [[[u8; 4]; 2]; 2] {
+        let table = lookup_table();
+        [0, 1].map(|y| [0, 1].map(|x| table.get_pixel(pattern * 2 + x, cross * 2 + y).0))
+    }
+
+    #[test]
+    fn blends_flat_areas_evenly() {
+        assert_eq!(tile(0, 0), [[[128, 64, 64, 0]; 2]; 2]);
+    }
+
+    #[test]
+    fn joins_diagonal_lines() {
+        // The source pixel is on a line from the top left to the bottom right,
+        // on a background of a single other color.
+        assert_eq!(
+            tile(0x7e, 0),
+            [
+                [[191, 0, 0, 64], [128, 64, 64, 0]],
+                [[128, 64, 64, 0], [191, 0, 0, 64]],
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_isolated_pixels_nearly_whole() {
+        assert_eq!(tile(0xff, 0), [[[223, 16, 16, 0]; 2]; 2]);
+    }
+}
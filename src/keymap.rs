@@ -0,0 +1,321 @@
+use crate::{fullscreen::FullscreenMode, scaling::ScaleMode};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use winit::event::VirtualKeyCode::{self, *};
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeymapError {
+    #[error("Could not read keymap {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Line {0} of the keymap is not an action = keys pair")]
+    Syntax(usize),
+    #[error("Unknown action \"{1}\" on line {0} of the keymap")]
+    UnknownAction(usize, String),
+    #[error("Unknown key \"{1}\" on line {0} of the keymap")]
+    UnknownKey(usize, String),
+}
+
+/// What a key does in the viewer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Scale by the next larger fixed factor.
+    ZoomIn,
+    /// Scale by the next smaller fixed factor.
+    ZoomOut,
+    /// Switch between fitting the window and the last fixed factor.
+    ToggleFit,
+    /// Scale in the given mode.
+    Mode(ScaleMode),
+    /// Go through no post-process, the CRT and the LCD.
+    CyclePostProcess,
+    /// Go back to the scale given on the command line, without any pan.
+    ResetView,
+    /// Switch snapping the window to whole multiples of the image on or off.
+    ToggleSnap,
+    /// Switch between the window and the given fullscreen mode.
+    ToggleFullscreen(FullscreenMode),
+    /// Show or hide the shader parameter panel.
+    ShowParameters,
+    /// Show or hide the checkerboard behind the image.
+    ToggleCheckerboard,
+    /// Switch between taking the image's alpha as straight and premultiplied.
+    TogglePremultiplied,
+    /// Show or hide the pixel grid.
+    TogglePixelGrid,
+}
+
+impl FromStr for Action {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zoom-in" => Ok(Self::ZoomIn),
+            "zoom-out" => Ok(Self::ZoomOut),
+            "toggle-fit" => Ok(Self::ToggleFit),
+            "cycle-post-process" => Ok(Self::CyclePostProcess),
+            "reset-view" => Ok(Self::ResetView),
+            "snap" => Ok(Self::ToggleSnap),
+            "fullscreen" => Ok(Self::ToggleFullscreen(FullscreenMode::Borderless)),
+            "exclusive-fullscreen" => Ok(Self::ToggleFullscreen(FullscreenMode::Exclusive)),
+            "parameters" => Ok(Self::ShowParameters),
+            "checkerboard" => Ok(Self::ToggleCheckerboard),
+            "premultiplied" => Ok(Self::TogglePremultiplied),
+            "pixel-grid" => Ok(Self::TogglePixelGrid),
+            _ => {
+                let mode = s.strip_prefix("mode-").ok_or(())?;
+                mode.parse().map(Self::Mode).map_err(|_| ())
+            }
+        }
+    }
+}
+
+/// Which key triggers which [`Action`].
+///
+/// A keymap file changes the defaults with `action = key, key` lines, e.g.
+/// `zoom-in = plus, numpadadd` or `mode-xbrz6 = 6`. Every line replaces the
+/// keys of its action, and an empty list unbinds it. Blank lines and lines
+/// starting with `#` are skipped. Keys are named after winit's virtual key
+/// codes, in any case, with plain digits for the number row.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(VirtualKeyCode, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let modes = [
+            ScaleMode::Integer,
+            ScaleMode::SharpBilinear,
+            ScaleMode::Coverage,
+            ScaleMode::Scale2x,
+            ScaleMode::Scale3x,
+            ScaleMode::Xbrz(4),
+            ScaleMode::Hq2x,
+        ];
+        let mut bindings = vec![
+            (Plus, Action::ZoomIn),
+            (Equals, Action::ZoomIn),
+            (NumpadAdd, Action::ZoomIn),
+            (Minus, Action::ZoomOut),
+            (NumpadSubtract, Action::ZoomOut),
+            (F, Action::ToggleFit),
+            (Tab, Action::CyclePostProcess),
+            (R, Action::ResetView),
+            (W, Action::ToggleSnap),
+            (F11, Action::ToggleFullscreen(FullscreenMode::Borderless)),
+            (F12, Action::ToggleFullscreen(FullscreenMode::Exclusive)),
+            (P, Action::ShowParameters),
+            (C, Action::ToggleCheckerboard),
+            (A, Action::TogglePremultiplied),
+            (G, Action::TogglePixelGrid),
+        ];
+        bindings.extend(
+            DIGITS[1..]
+                .iter()
+                .zip(modes)
+                .map(|(&key, mode)| (key, Action::Mode(mode))),
+        );
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// The default keymap changed by the file at `path`.
+    pub fn load(path: &Path) -> Result<Self, KeymapError> {
+        let text = fs::read_to_string(path).map_err(|e| KeymapError::Io(path.to_owned(), e))?;
+        Self::parse(&text)
+    }
+
+    /// The default keymap changed by the text of a keymap file.
+    pub fn parse(text: &str) -> Result<Self, KeymapError> {
+        let mut keymap = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let number = number + 1;
+            let (action, keys) = line.split_once('=').ok_or(KeymapError::Syntax(number))?;
+            let action = action.trim();
+            let action: Action = action
+                .parse()
+                .map_err(|()| KeymapError::UnknownAction(number, action.to_owned()))?;
+            keymap.bindings.retain(|&(_, bound)| bound != action);
+            for key in keys.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+                let key = parse_key(key)
+                    .ok_or_else(|| KeymapError::UnknownKey(number, key.to_owned()))?;
+                keymap.bindings.retain(|&(bound, _)| bound != key);
+                keymap.bindings.push((key, action));
+            }
+        }
+        Ok(keymap)
+    }
+
+    pub fn action(&self, key: VirtualKeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|&&(bound, _)| bound == key)
+            .map(|&(_, action)| action)
+    }
+}
+
+const DIGITS: [VirtualKeyCode; 10] = [Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9];
+const LETTERS: [VirtualKeyCode; 26] = [
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+];
+const FUNCTION_KEYS: [VirtualKeyCode; 24] = [
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12, F13, F14, F15, F16, F17, F18, F19, F20, F21,
+    F22, F23, F24,
+];
+const NUMPAD_DIGITS: [VirtualKeyCode; 10] = [
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+];
+
+/// The key named `name`, in any case.
+fn parse_key(name: &str) -> Option<VirtualKeyCode> {
+    let name = name.to_ascii_lowercase();
+    let indexed = |prefix: &str, keys: &[VirtualKeyCode], first: usize| {
+        let index: usize = name.strip_prefix(prefix)?.parse().ok()?;
+        keys.get(index.checked_sub(first)?).copied()
+    };
+    if let [c @ b'a'..=b'z'] = name.as_bytes() {
+        return Some(LETTERS[(c - b'a') as usize]);
+    }
+    if let Some(key) = indexed("", &DIGITS, 0)
+        .filter(|_| name.len() == 1)
+        .or_else(|| indexed("key", &DIGITS, 0).filter(|_| name.len() == 4))
+        .or_else(|| indexed("f", &FUNCTION_KEYS, 1))
+        .or_else(|| indexed("numpad", &NUMPAD_DIGITS, 0).filter(|_| name.len() == 7))
+    {
+        return Some(key);
+    }
+    let key = match name.as_str() {
+        "escape" => Escape,
+        "space" => Space,
+        "tab" => Tab,
+        "return" | "enter" => Return,
+        "back" | "backspace" => Back,
+        "insert" => Insert,
+        "delete" => Delete,
+        "home" => Home,
+        "end" => End,
+        "pageup" => PageUp,
+        "pagedown" => PageDown,
+        "left" => Left,
+        "right" => Right,
+        "up" => Up,
+        "down" => Down,
+        "plus" => Plus,
+        "minus" => Minus,
+        "equals" => Equals,
+        "asterisk" => Asterisk,
+        "comma" => Comma,
+        "period" => Period,
+        "slash" => Slash,
+        "backslash" => Backslash,
+        "colon" => Colon,
+        "semicolon" => Semicolon,
+        "apostrophe" => Apostrophe,
+        "grave" => Grave,
+        "lbracket" => LBracket,
+        "rbracket" => RBracket,
+        "numpadadd" => NumpadAdd,
+        "numpadsubtract" => NumpadSubtract,
+        "numpadmultiply" => NumpadMultiply,
+        "numpaddivide" => NumpadDivide,
+        "numpaddecimal" => NumpadDecimal,
+        "numpadcomma" => NumpadComma,
+        "numpadenter" => NumpadEnter,
+        "numpadequals" => NumpadEquals,
+        "pause" => Pause,
+        "snapshot" => Snapshot,
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binds_the_defaults() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.action(Plus), Some(Action::ZoomIn));
+        assert_eq!(keymap.action(Key1), Some(Action::Mode(ScaleMode::Integer)));
+        assert_eq!(keymap.action(Key7), Some(Action::Mode(ScaleMode::Hq2x)));
+        assert_eq!(keymap.action(Key8), None);
+    }
+
+    #[test]
+    fn parses_key_names_in_any_case() {
+        for (name, key) in [
+            ("a", A),
+            ("Z", Z),
+            ("7", Key7),
+            ("key0", Key0),
+            ("f1", F1),
+            ("F24", F24),
+            ("numpad5", Numpad5),
+            ("NumpadAdd", NumpadAdd),
+            ("Enter", Return),
+            ("backspace", Back),
+            ("pageup", PageUp),
+        ] {
+            assert_eq!(parse_key(name), Some(key), "{name}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_key_names() {
+        for name in [
+            "", "77", "key", "key10", "f0", "f25", "numpad", "numpad10", "ctrl", "é",
+        ] {
+            assert_eq!(parse_key(name), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn parses_changes_to_the_defaults() {
+        let keymap = Keymap::parse(
+            "# Zoom with the numpad only.\n\
+             \n\
+             zoom-in = numpadadd, NumpadMultiply\n\
+             mode-xbrz6 = 6\n\
+             toggle-fit =\n\
+             snap = f\n",
+        )
+        .unwrap();
+        assert_eq!(keymap.action(NumpadAdd), Some(Action::ZoomIn));
+        assert_eq!(keymap.action(NumpadMultiply), Some(Action::ZoomIn));
+        assert_eq!(keymap.action(Plus), None);
+        assert_eq!(keymap.action(Key6), Some(Action::Mode(ScaleMode::Xbrz(6))));
+        assert_eq!(keymap.action(F), Some(Action::ToggleSnap));
+        assert_eq!(keymap.action(W), None);
+        assert_eq!(keymap.action(Minus), Some(Action::ZoomOut));
+    }
+
+    #[test]
+    fn rejects_invalid_keymaps() {
+        let error = |text| Keymap::parse(text).unwrap_err().to_string();
+        assert_eq!(
+            error("zoom-in = plus\nzoom-out minus"),
+            "Line 2 of the keymap is not an action = keys pair"
+        );
+        assert_eq!(
+            error("zoom = plus"),
+            "Unknown action \"zoom\" on line 1 of the keymap"
+        );
+        assert_eq!(
+            error("mode-hq5x = 7"),
+            "Unknown action \"mode-hq5x\" on line 1 of the keymap"
+        );
+        assert_eq!(
+            error("# Hyper isn't a key winit knows.\nzoom-in = plus, hyper"),
+            "Unknown key \"hyper\" on line 2 of the keymap"
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{
+    blit::{self, Blit, ExtraBindings},
+    renderer::{self, Renderer},
+    scaling::Viewport,
+    texture::Texture,
+};
+use std::time::Instant;
+use winit::dpi::PhysicalSize;
+
+/// Frame rate that [`LcdSettings::ghosting`] is measured at.
+const GHOSTING_FRAME_RATE: f32 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LcdSettings {
+    /// How dark the lines between pixels get, from 0 to 1. They are one target
+    /// pixel wide, so they need an integer factor of at least 2 to show.
+    pub grid: f32,
+    /// How much each pixel is split into red, green and blue columns, from 0
+    /// to 1. They need a factor of at least 3 to show.
+    pub subpixels: f32,
+    /// How much of the previous frame is left on screen after a frame at 60
+    /// Hz, from 0 to 1.
+    pub ghosting: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct LcdUniforms {
+    image: [f32; 4],
+    source_size: [f32; 2],
+    grid: f32,
+    subpixels: f32,
+    history_weight: f32,
+    _padding: [f32; 3],
+}
+
+/// Post-process stage that draws a scaled image as if on a handheld's LCD.
+///
+/// The image is rendered into [`Lcd::input`], at the size of the final target.
+/// Every frame is drawn into one of two history textures, blending in the
+/// other from the previous frame, and then copied onto the target.
+pub struct Lcd {
+    settings: LcdSettings,
+    pipeline: wgpu::RenderPipeline,
+    copy_pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    uniform_buffer: wgpu::Buffer,
+    uniforms: LcdUniforms,
+    input: Texture,
+    history: [Texture; 2],
+    /// Draws into each history texture, reading the other one.
+    blits: [Blit; 2],
+    /// Copies each history texture onto the target.
+    copies: [Blit; 2],
+    frame: usize,
+    last_frame_time: Option<Instant>,
+}
+
+impl Lcd {
+    pub fn new(
+        device: &wgpu::Device,
+        renderer: &Renderer,
+        target_format: wgpu::TextureFormat,
+        settings: LcdSettings,
+    ) -> Self {
+        let blit_pipeline = renderer.blit_pipeline();
+        let pipeline =
+            blit_pipeline.create_pipeline(device, target_format, "fs_lcd", ExtraBindings::History);
+        let copy_pipeline =
+            blit_pipeline.create_pipeline(device, target_format, "fs_main", ExtraBindings::None);
+        let sampler = blit::create_sampler(device, wgpu::FilterMode::Nearest);
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("LCD uniform buffer"),
+            size: std::mem::size_of::<LcdUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniforms = LcdUniforms {
+            image: [0.0; 4],
+            source_size: [1.0; 2],
+            grid: settings.grid,
+            subpixels: settings.subpixels,
+            history_weight: 0.0,
+            _padding: [0.0; 3],
+        };
+        let (input, history, blits, copies) = Self::create_targets(
+            device,
+            renderer,
+            &uniform_buffer,
+            &sampler,
+            PhysicalSize::new(1, 1),
+            target_format,
+        );
+
+        Self {
+            settings,
+            pipeline,
+            copy_pipeline,
+            sampler,
+            uniform_buffer,
+            uniforms,
+            input,
+            history,
+            blits,
+            copies,
+            frame: 0,
+            last_frame_time: None,
+        }
+    }
+
+    fn create_targets(
+        device: &wgpu::Device,
+        renderer: &Renderer,
+        uniform_buffer: &wgpu::Buffer,
+        sampler: &wgpu::Sampler,
+        size: PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+    ) -> (Texture, [Texture; 2], [Blit; 2], [Blit; 2]) {
+        let blit_pipeline = renderer.blit_pipeline();
+        let input = Texture::render_target(device, size, format, Some("LCD input texture"));
+        let history = [(); 2]
+            .map(|_| Texture::render_target(device, size, format, Some("LCD history texture")));
+        let blits = [0, 1].map(|i| {
+            Blit::new(device, blit_pipeline, &input.view, sampler).with_history(
+                device,
+                blit_pipeline,
+                uniform_buffer,
+                &history[1 - i].view,
+            )
+        });
+        let copies = [0, 1].map(|i| Blit::new(device, blit_pipeline, &history[i].view, sampler));
+        (input, history, blits, copies)
+    }
+
+    /// Where to render the scaled image before [`Lcd::render`].
+    pub fn input(&self) -> &wgpu::TextureView {
+        &self.input.view
+    }
+
+    /// Sizes the input and history to the target, and places the image the way
+    /// `renderer` scaled it into the target. Resizing forgets the history.
+    pub fn set_layout(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        renderer: &Renderer,
+        viewport: Viewport,
+        target_size: PhysicalSize<u32>,
+    ) {
+        if self.input.size() != target_size {
+            (self.input, self.history, self.blits, self.copies) = Self::create_targets(
+                device,
+                renderer,
+                &self.uniform_buffer,
+                &self.sampler,
+                target_size,
+                self.input.texture.format(),
+            );
+            self.forget_history();
+        }
+        let whole_target = Viewport::covering(target_size);
+        for blit in self.blits.iter().chain(&self.copies) {
+            blit.set_viewport(queue, whole_target, target_size);
+        }
+
+        let source_size = renderer.source().size();
+        self.uniforms.image = [
+            viewport.x as f32,
+            viewport.y as f32,
+            viewport.width as f32,
+            viewport.height as f32,
+        ];
+        self.uniforms.source_size = [source_size.width as f32, source_size.height as f32];
+    }
+
+    /// Leaves the previous frame out of the next one.
+    pub fn forget_history(&mut self) {
+        self.last_frame_time = None;
+    }
+
+    /// Draws the next frame, ghosting the previous one by how long ago it was.
+    pub fn render(
+        &mut self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) {
+        let now = Instant::now();
+        self.uniforms.history_weight = match self.last_frame_time {
+            Some(last) if self.settings.ghosting > 0.0 => self
+                .settings
+                .ghosting
+                .powf((now - last).as_secs_f32() * GHOSTING_FRAME_RATE),
+            _ => 0.0,
+        };
+        self.last_frame_time = Some(now);
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&self.uniforms));
+
+        let current = self.frame % 2;
+        self.frame += 1;
+        {
+            let mut render_pass = renderer::begin_pass(
+                encoder,
+                &self.history[current].view,
+                wgpu::Color::TRANSPARENT,
+                "LCD pass",
+            );
+            self.blits[current].draw(&mut render_pass, &self.pipeline);
+        }
+        let mut render_pass =
+            renderer::begin_pass(encoder, view, wgpu::Color::TRANSPARENT, "LCD copy pass");
+        self.copies[current].draw(&mut render_pass, &self.copy_pipeline);
+    }
+}
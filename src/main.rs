@@ -1,8 +1,40 @@
-use std::error::Error;
+use clap::Parser;
+use cli::{Cli, Command};
 use pollster::FutureExt;
+use std::error::Error;
 
 mod app;
+mod batch;
+mod blit;
+mod checkerboard;
+mod cli;
+mod color;
+mod crt;
+mod fullscreen;
+mod gpu;
+mod headless;
+mod hqx;
+mod keymap;
+mod lcd;
+mod overlay;
+mod pixel_grid;
+mod post_process;
+mod preset;
+mod renderer;
+mod scaling;
+mod shader_chain;
+mod slang;
+mod srgb;
+mod texture;
+mod view;
+mod watch;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    app::run().block_on()
+    let cli = Cli::parse();
+    match (cli.command, cli.view) {
+        (Some(Command::Render(args)), _) => headless::render(args).block_on(),
+        (Some(Command::Batch(args)), _) => batch::run(args).block_on(),
+        (None, Some(args)) => app::run(args).block_on(),
+        (None, None) => unreachable!("clap requires either a command or viewer arguments"),
+    }
 }
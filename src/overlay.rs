@@ -0,0 +1,227 @@
+use crate::{
+    blit::{self, Blit, BlitPipeline, ExtraBindings},
+    renderer,
+    scaling::Viewport,
+    texture::Texture,
+};
+use winit::dpi::PhysicalSize;
+
+/// Columns of the 5x7 glyphs of the printable ASCII characters, from the
+/// space to `~`, the lowest bit at the top.
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x5f, 0x00, 0x00],
+    [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7f, 0x14, 0x7f, 0x14],
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12],
+    [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50],
+    [0x00, 0x05, 0x03, 0x00, 0x00],
+    [0x00, 0x1c, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1c, 0x00],
+    [0x08, 0x2a, 0x1c, 0x2a, 0x08],
+    [0x08, 0x08, 0x3e, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00],
+    [0x08, 0x08, 0x08, 0x08, 0x08],
+    [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02],
+    [0x3e, 0x51, 0x49, 0x45, 0x3e],
+    [0x00, 0x42, 0x7f, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46],
+    [0x21, 0x41, 0x45, 0x4b, 0x31],
+    [0x18, 0x14, 0x12, 0x7f, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39],
+    [0x3c, 0x4a, 0x49, 0x49, 0x30],
+    [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36],
+    [0x06, 0x49, 0x49, 0x29, 0x1e],
+    [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00],
+    [0x08, 0x14, 0x22, 0x41, 0x00],
+    [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08],
+    [0x02, 0x01, 0x51, 0x09, 0x06],
+    [0x32, 0x49, 0x79, 0x41, 0x3e],
+    [0x7e, 0x11, 0x11, 0x11, 0x7e],
+    [0x7f, 0x49, 0x49, 0x49, 0x36],
+    [0x3e, 0x41, 0x41, 0x41, 0x22],
+    [0x7f, 0x41, 0x41, 0x22, 0x1c],
+    [0x7f, 0x49, 0x49, 0x49, 0x41],
+    [0x7f, 0x09, 0x09, 0x09, 0x01],
+    [0x3e, 0x41, 0x49, 0x49, 0x7a],
+    [0x7f, 0x08, 0x08, 0x08, 0x7f],
+    [0x00, 0x41, 0x7f, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3f, 0x01],
+    [0x7f, 0x08, 0x14, 0x22, 0x41],
+    [0x7f, 0x40, 0x40, 0x40, 0x40],
+    [0x7f, 0x02, 0x0c, 0x02, 0x7f],
+    [0x7f, 0x04, 0x08, 0x10, 0x7f],
+    [0x3e, 0x41, 0x41, 0x41, 0x3e],
+    [0x7f, 0x09, 0x09, 0x09, 0x06],
+    [0x3e, 0x41, 0x51, 0x21, 0x5e],
+    [0x7f, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31],
+    [0x01, 0x01, 0x7f, 0x01, 0x01],
+    [0x3f, 0x40, 0x40, 0x40, 0x3f],
+    [0x1f, 0x20, 0x40, 0x20, 0x1f],
+    [0x3f, 0x40, 0x38, 0x40, 0x3f],
+    [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x07, 0x08, 0x70, 0x08, 0x07],
+    [0x61, 0x51, 0x49, 0x45, 0x43],
+    [0x00, 0x7f, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20],
+    [0x00, 0x41, 0x41, 0x7f, 0x00],
+    [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40],
+    [0x00, 0x01, 0x02, 0x04, 0x00],
+    [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7f, 0x48, 0x44, 0x44, 0x38],
+    [0x38, 0x44, 0x44, 0x44, 0x20],
+    [0x38, 0x44, 0x44, 0x48, 0x7f],
+    [0x38, 0x54, 0x54, 0x54, 0x18],
+    [0x08, 0x7e, 0x09, 0x01, 0x02],
+    [0x0c, 0x52, 0x52, 0x52, 0x3e],
+    [0x7f, 0x08, 0x04, 0x04, 0x78],
+    [0x00, 0x44, 0x7d, 0x40, 0x00],
+    [0x20, 0x40, 0x44, 0x3d, 0x00],
+    [0x7f, 0x10, 0x28, 0x44, 0x00],
+    [0x00, 0x41, 0x7f, 0x40, 0x00],
+    [0x7c, 0x04, 0x18, 0x04, 0x78],
+    [0x7c, 0x08, 0x04, 0x04, 0x78],
+    [0x38, 0x44, 0x44, 0x44, 0x38],
+    [0x7c, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7c],
+    [0x7c, 0x08, 0x04, 0x04, 0x08],
+    [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3f, 0x44, 0x40, 0x20],
+    [0x3c, 0x40, 0x40, 0x20, 0x7c],
+    [0x1c, 0x20, 0x40, 0x20, 0x1c],
+    [0x3c, 0x40, 0x30, 0x40, 0x3c],
+    [0x44, 0x28, 0x10, 0x28, 0x44],
+    [0x0c, 0x50, 0x50, 0x50, 0x3c],
+    [0x44, 0x64, 0x54, 0x4c, 0x44],
+    [0x00, 0x08, 0x36, 0x41, 0x00],
+    [0x00, 0x00, 0x7f, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00],
+    [0x10, 0x08, 0x08, 0x10, 0x08],
+];
+
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+/// Glyphs with the space between them and between lines.
+const CELL_WIDTH: u32 = GLYPH_WIDTH + 1;
+const CELL_HEIGHT: u32 = GLYPH_HEIGHT + 2;
+/// Pixels around the text, and between the overlay and the window's edges.
+const MARGIN: u32 = 4;
+/// Longer lines wrap.
+const MAX_COLUMNS: usize = 100;
+const MAX_LINES: usize = 40;
+/// Screen pixels per pixel of the font.
+const SCALE: u32 = 2;
+
+const BACKGROUND: image::Rgba<u8> = image::Rgba([96, 0, 0, 255]);
+const FOREGROUND: image::Rgba<u8> = image::Rgba([255, 255, 255, 255]);
+
+/// Corner of the window a [`TextOverlay`] sits in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    BottomLeft,
+}
+
+/// Box of text over a corner of the window, e.g. the error that kept a shader
+/// from reloading.
+pub struct TextOverlay {
+    corner: Corner,
+    texture: Texture,
+    blit: Blit,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl TextOverlay {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        blit_pipeline: &BlitPipeline,
+        target_format: wgpu::TextureFormat,
+        corner: Corner,
+        text: &str,
+    ) -> Self {
+        let texture = Texture::from_image(device, queue, &rasterize(text), Some("Overlay texture"));
+        let sampler = blit::create_sampler(device, wgpu::FilterMode::Nearest);
+        let blit = Blit::new(device, blit_pipeline, &texture.view, &sampler);
+        let pipeline =
+            blit_pipeline.create_pipeline(device, target_format, "fs_main", ExtraBindings::None);
+
+        Self {
+            corner,
+            texture,
+            blit,
+            pipeline,
+        }
+    }
+
+    pub fn set_layout(&self, queue: &wgpu::Queue, target_size: PhysicalSize<u32>) {
+        let (width, height) = (self.texture.width * SCALE, self.texture.height * SCALE);
+        let y = match self.corner {
+            Corner::TopLeft => MARGIN as i32,
+            Corner::BottomLeft => target_size.height as i32 - (height + MARGIN) as i32,
+        };
+        let viewport = Viewport {
+            x: MARGIN as i32,
+            y,
+            width,
+            height,
+        };
+        self.blit.set_viewport(queue, viewport, target_size);
+    }
+
+    /// Draws over what `view` already holds.
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let mut render_pass =
+            renderer::begin_pass_with_load(encoder, view, wgpu::LoadOp::Load, "Overlay pass");
+        self.blit.draw(&mut render_pass, &self.pipeline);
+    }
+}
+
+/// Draws `text` in the built-in font on an opaque background, wrapping long
+/// lines and cutting off the ones that don't fit.
+fn rasterize(text: &str) -> image::RgbaImage {
+    let mut lines: Vec<Vec<char>> = text
+        .lines()
+        .flat_map(|line| {
+            let chars: Vec<char> = line.replace('\t', "    ").chars().collect();
+            if chars.is_empty() {
+                vec![chars]
+            } else {
+                chars.chunks(MAX_COLUMNS).map(<[char]>::to_vec).collect()
+            }
+        })
+        .collect();
+    lines.truncate(MAX_LINES);
+    let columns = lines.iter().map(Vec::len).max().unwrap_or(0) as u32;
+
+    let mut image = image::RgbaImage::from_pixel(
+        columns * CELL_WIDTH + 2 * MARGIN - 1,
+        lines.len() as u32 * CELL_HEIGHT + 2 * MARGIN - 2,
+        BACKGROUND,
+    );
+    for (row, line) in lines.iter().enumerate() {
+        for (column, &c) in line.iter().enumerate() {
+            let glyph = match c {
+                ' '..='~' => &FONT[c as usize - ' ' as usize],
+                _ => &FONT['?' as usize - ' ' as usize],
+            };
+            let x = MARGIN + column as u32 * CELL_WIDTH;
+            let y = MARGIN + row as u32 * CELL_HEIGHT;
+            for (dx, bits) in glyph.iter().enumerate() {
+                for dy in 0..GLYPH_HEIGHT {
+                    if bits >> dy & 1 != 0 {
+                        image.put_pixel(x + dx as u32, y + dy, FOREGROUND);
+                    }
+                }
+            }
+        }
+    }
+    image
+}
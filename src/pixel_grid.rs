@@ -0,0 +1,103 @@
+use crate::{
+    blit::{Blit, BlitPipeline, ExtraBindings},
+    color::U8Color,
+    scaling::Viewport,
+};
+use winit::dpi::PhysicalSize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelGridSettings {
+    /// Zoom factor from which the grid shows, as below it the lines would
+    /// cover most of the image.
+    pub min_zoom: f64,
+    pub pixel_color: U8Color,
+    /// Size of the tiles in source pixels, if they get lines of their own.
+    pub tile_size: Option<PhysicalSize<u32>>,
+    pub tile_color: U8Color,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct PixelGridUniforms {
+    pixel_color: [f32; 4],
+    tile_color: [f32; 4],
+    pixel_size: [f32; 2],
+    tile_size: [f32; 2],
+}
+
+/// Lines one target pixel wide between the source pixels of the scaled image,
+/// and between its tiles, drawn over it once it is zoomed in far enough.
+pub struct PixelGrid {
+    settings: PixelGridSettings,
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    blit: Blit,
+    visible: bool,
+}
+
+impl PixelGrid {
+    pub fn new(
+        device: &wgpu::Device,
+        blit_pipeline: &BlitPipeline,
+        target_format: wgpu::TextureFormat,
+        settings: PixelGridSettings,
+    ) -> Self {
+        let pipeline = blit_pipeline.create_blended_pipeline(
+            device,
+            target_format,
+            "fs_pixel_grid",
+            ExtraBindings::Parameters,
+            wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        );
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pixel grid uniform buffer"),
+            size: std::mem::size_of::<PixelGridUniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let blit = Blit::procedural(device, blit_pipeline, &uniform_buffer, "Pixel grid");
+
+        Self {
+            settings,
+            pipeline,
+            uniform_buffer,
+            blit,
+            visible: false,
+        }
+    }
+
+    /// Lines up the grid with the source scaled into `viewport`, and shows it
+    /// if that zooms in far enough.
+    pub fn set_layout(
+        &mut self,
+        queue: &wgpu::Queue,
+        source: PhysicalSize<u32>,
+        viewport: Viewport,
+        target_size: PhysicalSize<u32>,
+    ) {
+        let zoom = viewport.height as f64 / source.height as f64;
+        self.visible = zoom >= self.settings.min_zoom;
+        let premultiplied = |color: U8Color| color.to_linear().premultiplied().to_array();
+        let tile_size = self
+            .settings
+            .tile_size
+            .map_or([0.0; 2], |size| [size.width as f32, size.height as f32]);
+        let uniforms = PixelGridUniforms {
+            pixel_color: premultiplied(self.settings.pixel_color),
+            tile_color: premultiplied(self.settings.tile_color),
+            pixel_size: [
+                viewport.width as f32 / source.width as f32,
+                viewport.height as f32 / source.height as f32,
+            ],
+            tile_size,
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+        self.blit.set_viewport(queue, viewport, target_size);
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if self.visible {
+            self.blit.draw(render_pass, &self.pipeline);
+        }
+    }
+}
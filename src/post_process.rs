@@ -0,0 +1,92 @@
+use crate::{
+    crt::{Crt, CrtSettings},
+    lcd::{Lcd, LcdSettings},
+    renderer::Renderer,
+    scaling::Viewport,
+};
+use winit::dpi::PhysicalSize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostProcessSettings {
+    Crt(CrtSettings),
+    Lcd(LcdSettings),
+}
+
+/// Optional stage between the scaled image and the render target. The image
+/// is rendered into [`PostProcess::input`] instead of the target, and
+/// [`PostProcess::render`] draws it from there.
+pub enum PostProcess {
+    Crt(Box<Crt>),
+    Lcd(Box<Lcd>),
+}
+
+impl PostProcess {
+    pub fn new(
+        device: &wgpu::Device,
+        renderer: &Renderer,
+        target_format: wgpu::TextureFormat,
+        settings: PostProcessSettings,
+    ) -> Self {
+        match settings {
+            PostProcessSettings::Crt(settings) => Self::Crt(Box::new(Crt::new(
+                device,
+                renderer,
+                target_format,
+                settings,
+            ))),
+            PostProcessSettings::Lcd(settings) => Self::Lcd(Box::new(Lcd::new(
+                device,
+                renderer,
+                target_format,
+                settings,
+            ))),
+        }
+    }
+
+    pub fn input(&self) -> &wgpu::TextureView {
+        match self {
+            Self::Crt(crt) => crt.input(),
+            Self::Lcd(lcd) => lcd.input(),
+        }
+    }
+
+    /// Matches the stage to the target and to where `renderer` places the
+    /// image in it.
+    pub fn set_layout(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        renderer: &Renderer,
+        viewport: Viewport,
+        target_size: PhysicalSize<u32>,
+        background: wgpu::Color,
+    ) {
+        match self {
+            Self::Crt(crt) => {
+                crt.set_layout(device, queue, renderer, viewport, target_size, background)
+            }
+            Self::Lcd(lcd) => lcd.set_layout(device, queue, renderer, viewport, target_size),
+        }
+    }
+
+    /// Makes the next frame start afresh, for stages that carry state from
+    /// one frame to the next.
+    pub fn forget_history(&mut self) {
+        match self {
+            Self::Crt(_) => {}
+            Self::Lcd(lcd) => lcd.forget_history(),
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+    ) {
+        match self {
+            Self::Crt(crt) => crt.render(encoder, view),
+            Self::Lcd(lcd) => lcd.render(queue, encoder, view),
+        }
+    }
+}
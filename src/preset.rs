@@ -0,0 +1,506 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PresetError {
+    #[error("Could not read preset {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Line {0} of the preset is not a key = value pair")]
+    Syntax(usize),
+    #[error("Preset is missing \"{0}\"")]
+    MissingKey(String),
+    #[error("Invalid value \"{value}\" for \"{key}\" in the preset")]
+    InvalidValue { key: String, value: String },
+}
+
+/// How the output of a pass is sized along one axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PassScale {
+    /// A multiple of the pass's input.
+    Source(f32),
+    /// A multiple of the rectangle the image is drawn into on the target.
+    Viewport(f32),
+    /// A fixed number of pixels.
+    Absolute(u32),
+}
+
+impl PassScale {
+    pub fn apply(self, input: u32, viewport: u32) -> u32 {
+        let size = match self {
+            Self::Source(factor) => (input as f32 * factor).round() as u32,
+            Self::Viewport(factor) => (viewport as f32 * factor).round() as u32,
+            Self::Absolute(size) => size,
+        };
+        size.max(1)
+    }
+}
+
+/// What a pass samples outside of its input texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    #[default]
+    ClampToEdge,
+    /// Transparent black, or the edge where the device can't do borders.
+    ClampToBorder,
+    Repeat,
+    MirroredRepeat,
+}
+
+impl FromStr for WrapMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp_to_edge" => Ok(Self::ClampToEdge),
+            "clamp_to_border" => Ok(Self::ClampToBorder),
+            "repeat" => Ok(Self::Repeat),
+            "mirrored_repeat" => Ok(Self::MirroredRepeat),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassPreset {
+    /// Shader file, resolved against the preset's directory.
+    pub shader: PathBuf,
+    pub scale_x: PassScale,
+    pub scale_y: PassScale,
+    /// Sample the input with linear instead of nearest filtering.
+    pub filter_linear: bool,
+    pub wrap_mode: WrapMode,
+    /// Render into a 16-bit float texture, for passes whose output goes
+    /// outside of 0 to 1 or needs more precision.
+    pub float_framebuffer: bool,
+    /// Render into an sRGB texture, so that the next pass reads back linear
+    /// colors. Ignored with `float_framebuffer`.
+    pub srgb_framebuffer: bool,
+    /// Name later passes can read the output of this one by.
+    pub alias: Option<String>,
+    /// The frame count this pass sees wraps around at this many frames.
+    pub frame_count_mod: Option<u32>,
+}
+
+/// Image that passes can read by name, e.g. a phosphor mask.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LookupTexture {
+    pub name: String,
+    /// Image file, resolved against the preset's directory.
+    pub path: PathBuf,
+    pub filter_linear: bool,
+    pub wrap_mode: WrapMode,
+}
+
+/// A chain of shader passes, in the `key = value` format of RetroArch's slang
+/// presets:
+///
+/// ```text
+/// shaders = 2
+/// shader0 = smooth.wgsl
+/// scale_type0 = source
+/// scale0 = 2
+/// shader1 = scanlines.wgsl
+/// filter_linear1 = true
+/// ```
+///
+/// Every pass takes `shaderN` and optionally `scale_typeN` (`source`,
+/// `viewport` or `absolute`, or per axis with `scale_type_xN` and
+/// `scale_type_yN`), `scaleN` (or `scale_xN` and `scale_yN`),
+/// `filter_linearN`, `wrap_modeN`, `float_framebufferN`,
+/// `srgb_framebufferN`, `aliasN` and `frame_count_modN`. The last pass
+/// defaults to the size of the viewport, the others to the size of their
+/// input.
+///
+/// `textures = "A;B"` lists lookup textures, each with its image as `A` and
+/// optionally `A_linear` and `A_wrap_mode`. `parameters = "a;b"` lists shader
+/// parameters the preset sets, each with its value as `a`. Unknown keys are
+/// ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    pub passes: Vec<PassPreset>,
+    pub textures: Vec<LookupTexture>,
+    /// Values overriding the defaults of shader parameters, by name.
+    pub parameters: Vec<(String, f32)>,
+}
+
+impl Preset {
+    pub fn load(path: &Path) -> Result<Self, PresetError> {
+        let text = fs::read_to_string(path).map_err(|e| PresetError::Io(path.to_owned(), e))?;
+        let values = parse_values(&text)?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+        Self::from_values(&values, directory)
+    }
+
+    fn from_values(
+        values: &HashMap<String, String>,
+        directory: &Path,
+    ) -> Result<Self, PresetError> {
+        let count: usize = required(values, "shaders")?;
+        if count == 0 {
+            return Err(invalid("shaders", "0"));
+        }
+        let passes = (0..count)
+            .map(|i| {
+                let shader: String = required(values, &format!("shader{i}"))?;
+                let default_type = if i + 1 == count { "viewport" } else { "source" };
+                let scale_type = optional(values, &format!("scale_type{i}"))?
+                    .unwrap_or_else(|| default_type.to_owned());
+                let scale: Option<f32> = optional(values, &format!("scale{i}"))?;
+                let axis_scale = |axis: &str| {
+                    let type_key = format!("scale_type_{axis}{i}");
+                    let scale_key = format!("scale_{axis}{i}");
+                    let scale_type = optional(values, &type_key)?.unwrap_or(scale_type.clone());
+                    let scale = optional(values, &scale_key)?.or(scale).unwrap_or(1.0);
+                    pass_scale(&type_key, &scale_type, &scale_key, scale)
+                };
+                Ok(PassPreset {
+                    shader: directory.join(shader),
+                    scale_x: axis_scale("x")?,
+                    scale_y: axis_scale("y")?,
+                    filter_linear: optional(values, &format!("filter_linear{i}"))?.unwrap_or(false),
+                    wrap_mode: optional(values, &format!("wrap_mode{i}"))?.unwrap_or_default(),
+                    float_framebuffer: optional(values, &format!("float_framebuffer{i}"))?
+                        .unwrap_or(false),
+                    srgb_framebuffer: optional(values, &format!("srgb_framebuffer{i}"))?
+                        .unwrap_or(false),
+                    alias: optional(values, &format!("alias{i}"))?,
+                    frame_count_mod: optional(values, &format!("frame_count_mod{i}"))?
+                        .filter(|&modulus| modulus > 0),
+                })
+            })
+            .collect::<Result<_, PresetError>>()?;
+        let textures = list(values, "textures")
+            .map(|name| {
+                let path: String = required(values, name)?;
+                Ok(LookupTexture {
+                    name: name.to_owned(),
+                    path: directory.join(path),
+                    filter_linear: optional(values, &format!("{name}_linear"))?.unwrap_or(false),
+                    wrap_mode: optional(values, &format!("{name}_wrap_mode"))?.unwrap_or_default(),
+                })
+            })
+            .collect::<Result<_, PresetError>>()?;
+        let parameters = list(values, "parameters")
+            .map(|name| Ok((name.to_owned(), required(values, name)?)))
+            .collect::<Result<_, PresetError>>()?;
+        Ok(Self {
+            passes,
+            textures,
+            parameters,
+        })
+    }
+
+    /// Rewrites the parameters the preset at `path` sets to `values`, keeping
+    /// everything else as it is. Drops the values it set for parameters that
+    /// `values` doesn't have, but not other lines that share their names.
+    pub fn save_parameters(path: &Path, values: &[(String, f32)]) -> Result<(), PresetError> {
+        let io_error = |e| PresetError::Io(path.to_owned(), e);
+        let text = fs::read_to_string(path).map_err(io_error)?;
+        let old_values = parse_values(&text)?;
+        let mut dropped: Vec<&str> = list(&old_values, "parameters").collect();
+        dropped.extend(values.iter().map(|(name, _)| name.as_str()));
+        dropped.push("parameters");
+
+        let mut saved = String::with_capacity(text.len());
+        for line in text.lines() {
+            let key = line.split_once('=').map(|(key, _)| key.trim());
+            if !key.is_some_and(|key| dropped.contains(&key)) {
+                saved.push_str(line);
+                saved.push('\n');
+            }
+        }
+        if !values.is_empty() {
+            let names: Vec<_> = values.iter().map(|(name, _)| name.as_str()).collect();
+            saved.push_str(&format!("parameters = \"{}\"\n", names.join(";")));
+            for (name, value) in values {
+                saved.push_str(&format!("{name} = {value}\n"));
+            }
+        }
+        fs::write(path, saved).map_err(io_error)
+    }
+}
+
+/// Reads `key = value` lines, skipping blank lines and `#` comments. Values
+/// may be quoted.
+fn parse_values(text: &str) -> Result<HashMap<String, String>, PresetError> {
+    let mut values = HashMap::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or(PresetError::Syntax(number + 1))?;
+        let value = value.trim();
+        let value = match value.strip_prefix('"') {
+            Some(quoted) => {
+                quoted
+                    .split_once('"')
+                    .ok_or(PresetError::Syntax(number + 1))?
+                    .0
+            }
+            None => value
+                .split_once('#')
+                .map_or(value, |(value, _)| value.trim_end()),
+        };
+        values.insert(key.trim().to_owned(), value.to_owned());
+    }
+    Ok(values)
+}
+
+fn pass_scale(
+    type_key: &str,
+    scale_type: &str,
+    scale_key: &str,
+    scale: f32,
+) -> Result<PassScale, PresetError> {
+    let absolute = scale_type == "absolute";
+    if !scale.is_finite() || scale <= 0.0 || (absolute && scale.fract() != 0.0) {
+        return Err(invalid(scale_key, &scale.to_string()));
+    }
+    match scale_type {
+        "source" => Ok(PassScale::Source(scale)),
+        "viewport" => Ok(PassScale::Viewport(scale)),
+        "absolute" => Ok(PassScale::Absolute(scale as u32)),
+        _ => Err(invalid(type_key, scale_type)),
+    }
+}
+
+/// Names in a `;`-separated list.
+fn list<'a>(values: &'a HashMap<String, String>, key: &str) -> impl Iterator<Item = &'a str> {
+    values
+        .get(key)
+        .into_iter()
+        .flat_map(|names| names.split(';'))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+fn optional<T: FromStr>(
+    values: &HashMap<String, String>,
+    key: &str,
+) -> Result<Option<T>, PresetError> {
+    values
+        .get(key)
+        .map(|value| value.parse().map_err(|_| invalid(key, value)))
+        .transpose()
+}
+
+fn required<T: FromStr>(values: &HashMap<String, String>, key: &str) -> Result<T, PresetError> {
+    optional(values, key)?.ok_or_else(|| PresetError::MissingKey(key.to_owned()))
+}
+
+fn invalid(key: &str, value: &str) -> PresetError {
+    PresetError::InvalidValue {
+        key: key.to_owned(),
+        value: value.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Preset, PresetError> {
+        Preset::from_values(&parse_values(text)?, Path::new("presets"))
+    }
+
+    /// Preset file of its own for every test, as they run in parallel.
+    fn temporary_preset(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "perfect-scale-{}-{name}.slangp",
+            std::process::id()
+        ));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn parses_passes_with_defaults() {
+        let preset = parse(
+            "shaders = 2\n\
+             shader0 = smooth.slang\n\
+             scale0 = 2\n\
+             shader1 = \"crt/scanlines.slang\"\n",
+        )
+        .unwrap();
+        assert_eq!(preset.passes.len(), 2);
+        let (first, last) = (&preset.passes[0], &preset.passes[1]);
+        assert_eq!(first.shader, Path::new("presets/smooth.slang"));
+        assert_eq!(first.scale_x, PassScale::Source(2.0));
+        assert_eq!(first.scale_y, PassScale::Source(2.0));
+        assert!(!first.filter_linear);
+        assert_eq!(first.wrap_mode, WrapMode::ClampToEdge);
+        assert_eq!(first.alias, None);
+        assert_eq!(last.shader, Path::new("presets/crt/scanlines.slang"));
+        assert_eq!(last.scale_x, PassScale::Viewport(1.0));
+        assert_eq!(last.scale_y, PassScale::Viewport(1.0));
+        assert!(preset.textures.is_empty());
+        assert!(preset.parameters.is_empty());
+    }
+
+    #[test]
+    fn parses_pass_options() {
+        let preset = parse(
+            "# Comments and blank lines are skipped.\n\
+             \n\
+             shaders = 1\n\
+             shader0 = pass.slang # trailing comment\n\
+             scale_type_x0 = absolute\n\
+             scale_x0 = 320\n\
+             scale_type_y0 = source\n\
+             scale_y0 = 1.5\n\
+             filter_linear0 = true\n\
+             wrap_mode0 = mirrored_repeat\n\
+             float_framebuffer0 = true\n\
+             srgb_framebuffer0 = true\n\
+             alias0 = \"First#Pass\"\n\
+             frame_count_mod0 = 0\n\
+             unknown_key = whatever\n",
+        )
+        .unwrap();
+        let pass = &preset.passes[0];
+        assert_eq!(pass.shader, Path::new("presets/pass.slang"));
+        assert_eq!(pass.scale_x, PassScale::Absolute(320));
+        assert_eq!(pass.scale_y, PassScale::Source(1.5));
+        assert!(pass.filter_linear);
+        assert_eq!(pass.wrap_mode, WrapMode::MirroredRepeat);
+        assert!(pass.float_framebuffer);
+        assert!(pass.srgb_framebuffer);
+        assert_eq!(pass.alias.as_deref(), Some("First#Pass"));
+        // A modulus of 0 means the frame count never wraps.
+        assert_eq!(pass.frame_count_mod, None);
+    }
+
+    #[test]
+    fn parses_textures_and_parameters() {
+        let preset = parse(
+            "shaders = 1\n\
+             shader0 = pass.slang\n\
+             textures = \"Mask; Noise\"\n\
+             Mask = masks/slot.png\n\
+             Mask_linear = true\n\
+             Mask_wrap_mode = repeat\n\
+             Noise = noise.png\n\
+             parameters = \"strength;;size\"\n\
+             strength = 0.25\n\
+             size = 3\n",
+        )
+        .unwrap();
+        assert_eq!(
+            preset.textures,
+            [
+                LookupTexture {
+                    name: "Mask".to_owned(),
+                    path: PathBuf::from("presets/masks/slot.png"),
+                    filter_linear: true,
+                    wrap_mode: WrapMode::Repeat,
+                },
+                LookupTexture {
+                    name: "Noise".to_owned(),
+                    path: PathBuf::from("presets/noise.png"),
+                    filter_linear: false,
+                    wrap_mode: WrapMode::ClampToEdge,
+                },
+            ]
+        );
+        assert_eq!(
+            preset.parameters,
+            [("strength".to_owned(), 0.25), ("size".to_owned(), 3.0)]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_presets() {
+        let error = |text: &str| parse(text).unwrap_err().to_string();
+        assert_eq!(error("shader0 = a.slang"), "Preset is missing \"shaders\"");
+        assert_eq!(
+            error("shaders = 0"),
+            "Invalid value \"0\" for \"shaders\" in the preset"
+        );
+        assert_eq!(
+            error("shaders = 2\nshader0 = a.slang"),
+            "Preset is missing \"shader1\""
+        );
+        assert_eq!(
+            error("shaders = 1\n\nshader0 a.slang"),
+            "Line 3 of the preset is not a key = value pair"
+        );
+        assert_eq!(
+            error("shaders = 1\nshader0 = \"a.slang"),
+            "Line 2 of the preset is not a key = value pair"
+        );
+        assert_eq!(
+            error("shaders = 1\nshader0 = a.slang\nscale_type0 = absolute\nscale0 = 1.5"),
+            "Invalid value \"1.5\" for \"scale_x0\" in the preset"
+        );
+        assert_eq!(
+            error("shaders = 1\nshader0 = a.slang\nscale0 = -1"),
+            "Invalid value \"-1\" for \"scale_x0\" in the preset"
+        );
+        assert_eq!(
+            error("shaders = 1\nshader0 = a.slang\nscale_type0 = window"),
+            "Invalid value \"window\" for \"scale_type_x0\" in the preset"
+        );
+        assert_eq!(
+            error("shaders = 1\nshader0 = a.slang\nwrap_mode0 = clamp"),
+            "Invalid value \"clamp\" for \"wrap_mode0\" in the preset"
+        );
+        assert_eq!(
+            error("shaders = 1\nshader0 = a.slang\nparameters = \"size\""),
+            "Preset is missing \"size\""
+        );
+    }
+
+    #[test]
+    fn saves_parameters_and_keeps_the_rest() {
+        let path = temporary_preset(
+            "save",
+            "# Scanlines\n\
+             shaders = 1\n\
+             shader0 = pass.slang\n\
+             parameters = \"strength;size\"\n\
+             strength = 0.25\n\
+             size = 3\n\
+             filter_linear0 = true\n",
+        );
+        let values = [("strength".to_owned(), 0.5), ("curve".to_owned(), 2.0)];
+        Preset::save_parameters(&path, &values).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let preset = Preset::load(&path).unwrap();
+        Preset::save_parameters(&path, &[]).unwrap();
+        let cleared = Preset::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(text.starts_with("# Scanlines\nshaders = 1\nshader0 = pass.slang\n"));
+        assert_eq!(preset.parameters, values);
+        assert!(preset.passes[0].filter_linear);
+        assert!(cleared.parameters.is_empty());
+        assert_eq!(cleared.passes, preset.passes);
+    }
+
+    #[test]
+    fn keeps_lines_that_share_a_parameter_name() {
+        // "mask" names a texture here, and a parameter of the shader too.
+        let path = temporary_preset(
+            "shared-name",
+            "shaders = 1\n\
+             shader0 = pass.slang\n\
+             textures = \"mask\"\n\
+             mask = mask.png\n\
+             parameters = \"strength\"\n\
+             strength = 0.25\n",
+        );
+        Preset::save_parameters(&path, &[]).unwrap();
+        let cleared = Preset::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(cleared.parameters.is_empty());
+        assert_eq!(cleared.textures[0].path, path.with_file_name("mask.png"));
+    }
+}
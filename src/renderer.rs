@@ -0,0 +1,437 @@
+use crate::{
+    blit::{self, Blit, BlitPipeline, ExtraBindings},
+    checkerboard::{Checkerboard, CheckerboardSettings},
+    hqx,
+    pixel_grid::{PixelGrid, PixelGridSettings},
+    scaling::{Filter, Layout, Upscaler, Viewport},
+    shader_chain::{ShaderChain, ShaderChainError},
+    texture::Texture,
+};
+use std::{collections::HashMap, path::Path};
+use winit::dpi::PhysicalSize;
+
+/// Format of the premultiplied source and the passes after it. Floats keep
+/// the colors of translucent pixels exact enough to divide by alpha again.
+const INTERMEDIATE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// How the colors of the source relate to its alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceAlpha {
+    /// Independent of it, as image files store them.
+    Straight,
+    /// Already multiplied by it.
+    Premultiplied,
+}
+
+/// How the scaled image is drawn over what the target holds, which gets
+/// premultiplied colors either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compositing {
+    /// Replace it, alpha and all, as when writing the image to a file.
+    Replace,
+    /// Blend over it.
+    Blend,
+}
+
+impl Compositing {
+    fn blend(self) -> wgpu::BlendState {
+        match self {
+            Self::Replace => wgpu::BlendState::REPLACE,
+            Self::Blend => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        }
+    }
+}
+
+/// One draw of the previous image (or the source) into an intermediate texture,
+/// through a pixel-art scaler or as a nearest-neighbour prescale.
+struct Pass {
+    upscaler: Option<Upscaler>,
+    entry_point: &'static str,
+    texture: Texture,
+    blit: Blit,
+}
+
+pub struct Renderer {
+    blit_pipeline: BlitPipeline,
+    pipelines: HashMap<(&'static str, wgpu::TextureFormat, Compositing), wgpu::RenderPipeline>,
+    /// hq2x lookup table, built the first time it is needed.
+    hqx_lookup_table: Option<Texture>,
+    target_format: wgpu::TextureFormat,
+    nearest_sampler: wgpu::Sampler,
+    linear_sampler: wgpu::Sampler,
+    source: Texture,
+    source_alpha: SourceAlpha,
+    /// The source with premultiplied colors, which every later stage filters
+    /// and blends.
+    premultiplied_source: Pass,
+    passes: Vec<Pass>,
+    /// Shader chain that replaces the passes of the scale mode, if loaded.
+    shader_chain: Option<ShaderChain>,
+    output: Blit,
+    output_entry_point: &'static str,
+    compositing: Compositing,
+    checkerboard: Option<Checkerboard>,
+    pixel_grid: Option<PixelGrid>,
+}
+
+impl Renderer {
+    pub fn new(device: &wgpu::Device, target_format: wgpu::TextureFormat, source: Texture) -> Self {
+        let blit_pipeline = BlitPipeline::new(device);
+        let nearest_sampler = blit::create_sampler(device, wgpu::FilterMode::Nearest);
+        let linear_sampler = blit::create_sampler(device, wgpu::FilterMode::Linear);
+        let output = Blit::new(device, &blit_pipeline, &source.view, &nearest_sampler);
+        let premultiplied_source =
+            premultiplied_source(device, &blit_pipeline, &nearest_sampler, &source);
+
+        Self {
+            blit_pipeline,
+            pipelines: HashMap::new(),
+            hqx_lookup_table: None,
+            target_format,
+            nearest_sampler,
+            linear_sampler,
+            source,
+            source_alpha: SourceAlpha::Straight,
+            premultiplied_source,
+            passes: Vec::new(),
+            shader_chain: None,
+            output,
+            output_entry_point: "fs_main",
+            compositing: Compositing::Replace,
+            checkerboard: None,
+            pixel_grid: None,
+        }
+    }
+
+    pub fn blit_pipeline(&self) -> &BlitPipeline {
+        &self.blit_pipeline
+    }
+
+    pub fn source(&self) -> &Texture {
+        &self.source
+    }
+
+    /// Swaps in a new source image, keeping the pipelines. Call
+    /// [`Renderer::set_layout`] before rendering again.
+    pub fn set_source(&mut self, device: &wgpu::Device, source: Texture) {
+        self.premultiplied_source =
+            premultiplied_source(device, &self.blit_pipeline, &self.nearest_sampler, &source);
+        self.source = source;
+        self.passes.clear();
+    }
+
+    /// Scales the source through the shader chain described by the preset at
+    /// `path` instead of the passes of the scale mode, which still places and
+    /// filters the chain's output. Call [`Renderer::set_layout`] before
+    /// rendering again. Keeps the current chain if the new one fails to load.
+    pub fn load_shader_chain(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: &Path,
+    ) -> Result<(), ShaderChainError> {
+        self.shader_chain = Some(ShaderChain::load(device, queue, &self.blit_pipeline, path)?);
+        Ok(())
+    }
+
+    pub fn shader_chain(&self) -> Option<&ShaderChain> {
+        self.shader_chain.as_ref()
+    }
+
+    pub fn shader_chain_mut(&mut self) -> Option<&mut ShaderChain> {
+        self.shader_chain.as_mut()
+    }
+
+    /// Call [`Renderer::set_layout`] before rendering again.
+    pub fn set_source_alpha(&mut self, source_alpha: SourceAlpha) {
+        self.source_alpha = source_alpha;
+    }
+
+    /// Call [`Renderer::set_layout`] before rendering again.
+    pub fn set_compositing(&mut self, compositing: Compositing) {
+        self.compositing = compositing;
+    }
+
+    /// Draws a checkerboard behind the image, or stops drawing one if `None`.
+    /// Call [`Renderer::set_layout`] before rendering again.
+    pub fn set_checkerboard(
+        &mut self,
+        device: &wgpu::Device,
+        settings: Option<CheckerboardSettings>,
+    ) {
+        self.checkerboard = settings.map(|settings| {
+            Checkerboard::new(device, &self.blit_pipeline, self.target_format, settings)
+        });
+    }
+
+    /// Draws a pixel grid over the image, or stops drawing one if `None`. Call
+    /// [`Renderer::set_layout`] before rendering again.
+    pub fn set_pixel_grid(&mut self, device: &wgpu::Device, settings: Option<PixelGridSettings>) {
+        self.pixel_grid = settings.map(|settings| {
+            PixelGrid::new(device, &self.blit_pipeline, self.target_format, settings)
+        });
+    }
+
+    pub fn set_layout(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layout: Layout,
+        target_size: PhysicalSize<u32>,
+    ) {
+        self.set_source_alpha_pass(device, queue);
+        let wanted = match &mut self.shader_chain {
+            Some(shader_chain) => {
+                shader_chain.set_layout(
+                    device,
+                    queue,
+                    &self.blit_pipeline,
+                    &self.premultiplied_source.texture,
+                    layout.viewport.size(),
+                );
+                Vec::new()
+            }
+            None => layout.passes(self.source.size()),
+        };
+        self.set_passes(device, queue, &wanted);
+
+        let input = match &self.shader_chain {
+            Some(shader_chain) => shader_chain.output(),
+            None => self
+                .passes
+                .last()
+                .map_or(&self.premultiplied_source.texture, |pass| &pass.texture),
+        };
+        let sampler = match layout.filter {
+            Filter::Linear => &self.linear_sampler,
+            Filter::Nearest | Filter::Coverage => &self.nearest_sampler,
+        };
+        self.output = Blit::new(device, &self.blit_pipeline, &input.view, sampler);
+        self.output
+            .set_viewport(queue, layout.viewport, target_size);
+        self.output_entry_point = match layout.filter {
+            Filter::Nearest | Filter::Linear => "fs_main",
+            Filter::Coverage => "fs_coverage",
+        };
+        self.ensure_pipeline(
+            device,
+            self.output_entry_point,
+            self.target_format,
+            ExtraBindings::None,
+            self.compositing,
+        );
+        if let Some(checkerboard) = &self.checkerboard {
+            checkerboard.set_layout(queue, self.source.size(), layout.viewport, target_size);
+        }
+        if let Some(pixel_grid) = &mut self.pixel_grid {
+            pixel_grid.set_layout(queue, self.source.size(), layout.viewport, target_size);
+        }
+    }
+
+    /// Premultiplies the source, unless its colors already are.
+    fn set_source_alpha_pass(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let entry_point = match self.source_alpha {
+            SourceAlpha::Straight => "fs_premultiply",
+            SourceAlpha::Premultiplied => "fs_main",
+        };
+        self.ensure_pipeline(
+            device,
+            entry_point,
+            INTERMEDIATE_FORMAT,
+            ExtraBindings::None,
+            Compositing::Replace,
+        );
+        let pass = &mut self.premultiplied_source;
+        pass.entry_point = entry_point;
+        let size = pass.texture.size();
+        pass.blit
+            .set_viewport(queue, Viewport::covering(size), size);
+    }
+
+    /// Rebuilds the intermediate passes, keeping their textures when nothing changed.
+    fn set_passes(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        wanted: &[(Option<Upscaler>, PhysicalSize<u32>)],
+    ) {
+        let unchanged = self.passes.len() == wanted.len()
+            && self
+                .passes
+                .iter()
+                .zip(wanted)
+                .all(|(pass, &(upscaler, size))| {
+                    pass.upscaler == upscaler && pass.texture.size() == size
+                });
+        if unchanged {
+            return;
+        }
+
+        self.passes.clear();
+        for &(upscaler, size) in wanted {
+            let hq2x = upscaler == Some(Upscaler::Hq2x);
+            let entry_point = upscaler.map_or("fs_main", Upscaler::entry_point);
+            self.ensure_pipeline(
+                device,
+                entry_point,
+                INTERMEDIATE_FORMAT,
+                if hq2x {
+                    ExtraBindings::LookupTable
+                } else {
+                    ExtraBindings::None
+                },
+                Compositing::Replace,
+            );
+            let lookup_table = hq2x.then(|| {
+                &*self.hqx_lookup_table.get_or_insert_with(|| {
+                    Texture::from_image_with_format(
+                        device,
+                        queue,
+                        &hqx::lookup_table(),
+                        wgpu::TextureFormat::Rgba8Unorm,
+                        Some("hq2x lookup table"),
+                    )
+                })
+            });
+
+            let input = self
+                .passes
+                .last()
+                .map_or(&self.premultiplied_source.texture, |pass| &pass.texture);
+            let mut blit = Blit::new(
+                device,
+                &self.blit_pipeline,
+                &input.view,
+                &self.nearest_sampler,
+            );
+            if let Some(lookup_table) = lookup_table {
+                blit = blit.with_lookup_table(device, &self.blit_pipeline, &lookup_table.view);
+            }
+            blit.set_viewport(queue, Viewport::covering(size), size);
+            let texture =
+                Texture::render_target(device, size, INTERMEDIATE_FORMAT, Some("Pass texture"));
+            self.passes.push(Pass {
+                upscaler,
+                entry_point,
+                texture,
+                blit,
+            });
+        }
+    }
+
+    fn ensure_pipeline(
+        &mut self,
+        device: &wgpu::Device,
+        entry_point: &'static str,
+        format: wgpu::TextureFormat,
+        extra_bindings: ExtraBindings,
+        compositing: Compositing,
+    ) {
+        let blit_pipeline = &self.blit_pipeline;
+        self.pipelines
+            .entry((entry_point, format, compositing))
+            .or_insert_with(|| {
+                blit_pipeline.create_blended_pipeline(
+                    device,
+                    format,
+                    entry_point,
+                    extra_bindings,
+                    compositing.blend(),
+                )
+            });
+    }
+
+    fn draw_pass(&self, encoder: &mut wgpu::CommandEncoder, pass: &Pass) {
+        let mut render_pass = begin_pass(
+            encoder,
+            &pass.texture.view,
+            wgpu::Color::TRANSPARENT,
+            "Intermediate pass",
+        );
+        pass.blit.draw(
+            &mut render_pass,
+            &self.pipelines[&(pass.entry_point, INTERMEDIATE_FORMAT, Compositing::Replace)],
+        );
+    }
+
+    pub fn render(
+        &mut self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        clear_color: wgpu::Color,
+    ) {
+        self.draw_pass(encoder, &self.premultiplied_source);
+        if let Some(shader_chain) = &mut self.shader_chain {
+            shader_chain.render(queue, encoder);
+        }
+        for pass in &self.passes {
+            self.draw_pass(encoder, pass);
+        }
+
+        let mut render_pass = begin_pass(encoder, view, clear_color, "Render pass");
+        if let Some(checkerboard) = &self.checkerboard {
+            checkerboard.draw(&mut render_pass);
+        }
+        self.output.draw(
+            &mut render_pass,
+            &self.pipelines[&(
+                self.output_entry_point,
+                self.target_format,
+                self.compositing,
+            )],
+        );
+        if let Some(pixel_grid) = &self.pixel_grid {
+            pixel_grid.draw(&mut render_pass);
+        }
+    }
+}
+
+/// Pass that draws `source` into a texture of its size, for
+/// [`Renderer::set_layout`] to pick how.
+fn premultiplied_source(
+    device: &wgpu::Device,
+    blit_pipeline: &BlitPipeline,
+    sampler: &wgpu::Sampler,
+    source: &Texture,
+) -> Pass {
+    let texture = Texture::render_target(
+        device,
+        source.size(),
+        INTERMEDIATE_FORMAT,
+        Some("Premultiplied source texture"),
+    );
+    Pass {
+        upscaler: None,
+        entry_point: "fs_premultiply",
+        texture,
+        blit: Blit::new(device, blit_pipeline, &source.view, sampler),
+    }
+}
+
+pub fn begin_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    view: &'a wgpu::TextureView,
+    clear_color: wgpu::Color,
+    label: &str,
+) -> wgpu::RenderPass<'a> {
+    begin_pass_with_load(encoder, view, wgpu::LoadOp::Clear(clear_color), label)
+}
+
+/// Like [`begin_pass`], e.g. to draw over what `view` already holds.
+pub fn begin_pass_with_load<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    view: &'a wgpu::TextureView,
+    load: wgpu::LoadOp<wgpu::Color>,
+    label: &str,
+) -> wgpu::RenderPass<'a> {
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations { load, store: true },
+        })],
+        depth_stencil_attachment: None,
+    })
+}
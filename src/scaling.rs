@@ -0,0 +1,697 @@
+use std::str::FromStr;
+use winit::dpi::PhysicalSize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleMode {
+    /// Largest whole multiple of the source that fits, with borders around it.
+    #[default]
+    Integer,
+    /// Nearest-neighbour prescale to the next whole multiple, then a linear
+    /// downscale to the largest size that fits.
+    SharpBilinear,
+    /// Largest size that fits, at any fractional factor, with every output
+    /// pixel averaging the source texels it covers by area.
+    Coverage,
+    /// AdvMAME Scale2x edge smoothing, then integer scaling.
+    Scale2x,
+    /// AdvMAME Scale3x edge smoothing, then integer scaling.
+    Scale3x,
+    /// xBRZ edge smoothing at the given factor, from 2 to 6, then integer
+    /// scaling.
+    Xbrz(u32),
+    /// hq2x edge smoothing, then integer scaling.
+    Hq2x,
+}
+
+impl ScaleMode {
+    fn upscaler(self) -> Option<Upscaler> {
+        match self {
+            Self::Integer | Self::SharpBilinear | Self::Coverage => None,
+            Self::Scale2x => Some(Upscaler::Scale2x),
+            Self::Scale3x => Some(Upscaler::Scale3x),
+            Self::Xbrz(factor) => Some(Upscaler::Xbrz(factor)),
+            Self::Hq2x => Some(Upscaler::Hq2x),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "Unknown scale mode \"{0}\", expected one of: integer, sharp-bilinear, coverage, scale2x, scale3x, hq2x, xbrz2 to xbrz6"
+)]
+pub struct ParseScaleModeError(String);
+
+impl FromStr for ScaleMode {
+    type Err = ParseScaleModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "integer" => Ok(Self::Integer),
+            "sharp-bilinear" => Ok(Self::SharpBilinear),
+            "coverage" => Ok(Self::Coverage),
+            "scale2x" => Ok(Self::Scale2x),
+            "scale3x" => Ok(Self::Scale3x),
+            "hq2x" => Ok(Self::Hq2x),
+            _ => s
+                .strip_prefix("xbrz")
+                .and_then(|factor| factor.parse().ok())
+                .filter(|factor| (2..=6).contains(factor))
+                .map(Self::Xbrz)
+                .ok_or_else(|| ParseScaleModeError(s.to_owned())),
+        }
+    }
+}
+
+/// Width of a source pixel divided by its height, e.g. 8:7 for NES output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelAspect(pub f64);
+
+impl Default for PixelAspect {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid pixel aspect ratio \"{0}\", expected a positive number or a ratio like 8:7")]
+pub struct ParsePixelAspectError(String);
+
+impl FromStr for PixelAspect {
+    type Err = ParsePixelAspectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ratio = match s.split_once(':') {
+            Some((width, height)) => width
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .zip(height.trim().parse::<f64>().ok())
+                .map(|(width, height)| width / height),
+            None => s.trim().parse().ok(),
+        };
+        match ratio {
+            Some(ratio) if ratio.is_finite() && ratio > 0.0 => Ok(Self(ratio)),
+            _ => Err(ParsePixelAspectError(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Scaling {
+    pub mode: ScaleMode,
+    pub pixel_aspect: PixelAspect,
+    /// In integer mode, only scale by whole multiples vertically and reach the
+    /// exact pixel aspect horizontally with sharp-bilinear filtering.
+    pub aspect_fallback: bool,
+    /// Fixed factor to scale by instead of fitting the target. Integer mode
+    /// rounds it to the nearest whole number.
+    pub scale: Option<f64>,
+    /// Run the pixel-art scaler repeatedly until it reaches the integer
+    /// factor, instead of once and leaving the rest to the final filter.
+    pub chain: bool,
+}
+
+impl Scaling {
+    pub fn layout(&self, source: PhysicalSize<u32>, target: PhysicalSize<u32>) -> Layout {
+        let pixel_aspect = self.pixel_aspect.0;
+        match self.mode {
+            ScaleMode::Integer if self.aspect_fallback => {
+                let factor = match self.scale {
+                    Some(scale) => whole_factor(scale),
+                    None => (target.height / source.height)
+                        .min((target.width as f64 / (source.width as f64 * pixel_aspect)) as u32)
+                        .max(1),
+                };
+                let width = (source.width as f64 * factor as f64 * pixel_aspect).round() as u32;
+                let viewport =
+                    Viewport::centered(width.max(1), source.height.saturating_mul(factor), target);
+                Layout {
+                    viewport,
+                    prescale: Some(PhysicalSize::new(
+                        viewport.width.div_ceil(source.width).max(1),
+                        factor,
+                    )),
+                    upscalers: Vec::new(),
+                    filter: Filter::Linear,
+                }
+            }
+            ScaleMode::Integer => Layout {
+                viewport: self.fit_integer(source, target).0,
+                prescale: None,
+                upscalers: Vec::new(),
+                filter: Filter::Nearest,
+            },
+            ScaleMode::SharpBilinear => {
+                let viewport = self.fit(source, target);
+                Layout {
+                    viewport,
+                    prescale: Some(PhysicalSize::new(
+                        viewport.width.div_ceil(source.width).max(1),
+                        viewport.height.div_ceil(source.height).max(1),
+                    )),
+                    upscalers: Vec::new(),
+                    filter: Filter::Linear,
+                }
+            }
+            ScaleMode::Coverage => Layout {
+                viewport: self.fit(source, target),
+                prescale: None,
+                upscalers: Vec::new(),
+                filter: Filter::Coverage,
+            },
+            // The upscaled image is drawn at the integer factor, which covers
+            // it exactly whenever that is a multiple of the upscalers' factors.
+            ScaleMode::Scale2x | ScaleMode::Scale3x | ScaleMode::Xbrz(_) | ScaleMode::Hq2x => {
+                let (viewport, factors) = self.fit_integer(source, target);
+                Layout {
+                    viewport,
+                    prescale: None,
+                    upscalers: self.upscalers(factors.width.max(factors.height)),
+                    filter: Filter::Coverage,
+                }
+            }
+        }
+    }
+
+    /// Size of the image at the whole factor that comes closest to `target`
+    /// on the axis that limits the image, which the target can take to show
+    /// the image without borders. Only goes beyond `bounds` at a factor of 1.
+    pub fn snapped_size(
+        &self,
+        source: PhysicalSize<u32>,
+        target: PhysicalSize<u32>,
+        bounds: PhysicalSize<u32>,
+    ) -> PhysicalSize<u32> {
+        let distance = |size: PhysicalSize<u32>| {
+            target.width.abs_diff(size.width) + target.height.abs_diff(size.height)
+        };
+        // Factors past the axis that limits the image only get closer on the
+        // other axis while overflowing this one.
+        let limit = (target.width as f64 / (source.width as f64 * self.pixel_aspect.0))
+            .min(target.height as f64 / source.height as f64) as u32;
+        (1..=limit + 1)
+            .map(|factor| {
+                let scaling = Self {
+                    scale: Some(factor as f64),
+                    ..*self
+                };
+                scaling.layout(source, target).viewport.size()
+            })
+            .enumerate()
+            .filter(|&(i, size)| {
+                i == 0 || (size.width <= bounds.width && size.height <= bounds.height)
+            })
+            .map(|(_, size)| size)
+            .min_by_key(|&size| distance(size))
+            .expect("there is at least one factor")
+    }
+
+    /// Largest fixed scale, a whole multiple of `unit`, at which neither the
+    /// image, as wide as the pixel aspect makes it, nor the intermediate
+    /// texture of any pass is larger than `max_size` on a side. `None` if even
+    /// `unit` needs larger passes.
+    pub fn max_scale(
+        &self,
+        source: PhysicalSize<u32>,
+        target: PhysicalSize<u32>,
+        unit: f64,
+        max_size: u32,
+    ) -> Option<f64> {
+        let displayed = (source.width as f64 * self.pixel_aspect.0).max(source.height as f64);
+        let levels = (max_size as f64 / displayed / unit) as u32;
+        (1..=levels.max(1))
+            .rev()
+            .map(|level| level as f64 * unit)
+            .find(|&scale| {
+                let scaling = Self {
+                    scale: Some(scale),
+                    ..*self
+                };
+                scaling.layout(source, target).fits(source, max_size)
+            })
+    }
+
+    /// One pass of the mode's pixel-art scaler or, when chaining, as many as
+    /// it takes to reach `factor`. xBRZ passes shrink to the smallest factor
+    /// that still gets there.
+    fn upscalers(&self, factor: u32) -> Vec<Upscaler> {
+        let Some(upscaler) = self.mode.upscaler() else {
+            return Vec::new();
+        };
+        if !self.chain {
+            return vec![upscaler];
+        }
+
+        let mut upscalers = Vec::new();
+        let mut reached = 1;
+        loop {
+            let pass = match upscaler {
+                Upscaler::Xbrz(max) => Upscaler::Xbrz(factor.div_ceil(reached).clamp(2, max)),
+                other => other,
+            };
+            upscalers.push(pass);
+            reached = reached.saturating_mul(pass.factor());
+            if reached >= factor {
+                return upscalers;
+            }
+        }
+    }
+
+    /// Viewport at whole factors of the source, and those factors.
+    fn fit_integer(
+        &self,
+        source: PhysicalSize<u32>,
+        target: PhysicalSize<u32>,
+    ) -> (Viewport, PhysicalSize<u32>) {
+        let factors = match self.scale {
+            Some(scale) => {
+                let factor = whole_factor(scale);
+                PhysicalSize::new(whole_factor(factor as f64 * self.pixel_aspect.0), factor)
+            }
+            None => integer_factors(source, self.pixel_aspect.0, target),
+        };
+        // Fixed scales can be far too large for any texture, which the
+        // renderer's callers check for rather than overflowing here.
+        let viewport = Viewport::centered(
+            source.width.saturating_mul(factors.width),
+            source.height.saturating_mul(factors.height),
+            target,
+        );
+        (viewport, factors)
+    }
+
+    fn fit(&self, source: PhysicalSize<u32>, target: PhysicalSize<u32>) -> Viewport {
+        match self.scale {
+            Some(scale) => Viewport::centered(
+                ((source.width as f64 * self.pixel_aspect.0 * scale).round() as u32).max(1),
+                ((source.height as f64 * scale).round() as u32).max(1),
+                target,
+            ),
+            None => fit(source, self.pixel_aspect.0, target),
+        }
+    }
+}
+
+fn whole_factor(scale: f64) -> u32 {
+    (scale.round() as u32).max(1)
+}
+
+/// How the source is drawn for a given render target size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub viewport: Viewport,
+    /// Whole-number factors of a nearest-neighbour pass into an intermediate
+    /// texture, which is then filtered linearly into the viewport.
+    pub prescale: Option<PhysicalSize<u32>>,
+    /// Pixel-art scaler passes run on the source, in order, before it is
+    /// drawn into the viewport.
+    pub upscalers: Vec<Upscaler>,
+    /// How the (possibly prescaled or upscaled) source is sampled when drawn
+    /// into the viewport.
+    pub filter: Filter,
+}
+
+impl Layout {
+    /// The upscaler, or `None` for the prescale, and the size of the
+    /// intermediate texture of every pass before the image is drawn into the
+    /// viewport.
+    pub fn passes(&self, source: PhysicalSize<u32>) -> Vec<(Option<Upscaler>, PhysicalSize<u32>)> {
+        let scale = |size: PhysicalSize<u32>, factors: PhysicalSize<u32>| {
+            PhysicalSize::new(
+                size.width.saturating_mul(factors.width),
+                size.height.saturating_mul(factors.height),
+            )
+        };
+        let mut passes = Vec::new();
+        let mut size = source;
+        for &upscaler in &self.upscalers {
+            size = scale(
+                size,
+                PhysicalSize::new(upscaler.factor(), upscaler.factor()),
+            );
+            passes.push((Some(upscaler), size));
+        }
+        if let Some(factors) = self.prescale {
+            passes.push((None, scale(size, factors)));
+        }
+        passes
+    }
+
+    /// Whether the intermediate texture of every pass is at most `max_size`
+    /// on a side.
+    pub fn fits(&self, source: PhysicalSize<u32>, max_size: u32) -> bool {
+        self.passes(source)
+            .iter()
+            .all(|&(_, size)| size.width <= max_size && size.height <= max_size)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upscaler {
+    Scale2x,
+    Scale3x,
+    Xbrz(u32),
+    Hq2x,
+}
+
+impl Upscaler {
+    pub fn factor(self) -> u32 {
+        match self {
+            Self::Scale2x | Self::Hq2x => 2,
+            Self::Scale3x => 3,
+            Self::Xbrz(factor) => factor,
+        }
+    }
+
+    /// Fragment shader entry point that runs one pass of the scaler.
+    pub fn entry_point(self) -> &'static str {
+        match self {
+            Self::Scale2x => "fs_scale2x",
+            Self::Scale3x => "fs_scale3x",
+            // The shader reads its factor from the size of the target.
+            Self::Xbrz(_) => "fs_xbrz",
+            Self::Hq2x => "fs_hqx",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Linear,
+    Coverage,
+}
+
+/// Placement of the scaled image inside the render target, in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Viewport {
+    /// The whole of a target of `size`.
+    pub fn covering(size: PhysicalSize<u32>) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: size.width,
+            height: size.height,
+        }
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.width, self.height)
+    }
+
+    fn centered(width: u32, height: u32, target: PhysicalSize<u32>) -> Self {
+        let offset = |target: u32, size: u32| {
+            let offset = (target as i64 - size as i64) / 2;
+            offset.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+        };
+        Self {
+            x: offset(target.width, width),
+            y: offset(target.height, height),
+            width,
+            height,
+        }
+    }
+}
+
+/// How far, as a fraction, integer factors may stray from the requested pixel
+/// aspect before a smaller but more accurate pair is preferred.
+const ASPECT_TOLERANCE: f64 = 0.05;
+
+/// Whole X and Y factors that fit inside the target and best approximate the
+/// pixel aspect, never less than 1.
+///
+/// The largest pair within [`ASPECT_TOLERANCE`] of the pixel aspect wins. If
+/// there is none, the most accurate pair does, preferring larger ones on ties.
+pub fn integer_factors(
+    source: PhysicalSize<u32>,
+    pixel_aspect: f64,
+    target: PhysicalSize<u32>,
+) -> PhysicalSize<u32> {
+    let max_x = (target.width / source.width).max(1);
+    let max_y = (target.height / source.height).max(1);
+    let aspect_error = |x: u32, y: u32| (x as f64 / y as f64 / pixel_aspect).ln().abs();
+
+    let mut best = PhysicalSize::new(1, 1);
+    let mut best_error = aspect_error(1, 1);
+    for y in 1..=max_y {
+        let ideal_x = y as f64 * pixel_aspect;
+        for x in [ideal_x.floor() as u32, ideal_x.ceil() as u32] {
+            if x == 0 || x > max_x {
+                continue;
+            }
+            let error = aspect_error(x, y);
+            let area = x * y;
+            let best_area = best.width * best.height;
+            let better = if error <= ASPECT_TOLERANCE || best_error <= ASPECT_TOLERANCE {
+                error <= ASPECT_TOLERANCE && (best_error > ASPECT_TOLERANCE || area > best_area)
+            } else {
+                error < best_error || (error == best_error && area > best_area)
+            };
+            if better {
+                best = PhysicalSize::new(x, y);
+                best_error = error;
+            }
+        }
+    }
+    best
+}
+
+/// Largest size with the source's displayed aspect ratio that fits inside the target.
+pub fn fit(source: PhysicalSize<u32>, pixel_aspect: f64, target: PhysicalSize<u32>) -> Viewport {
+    let width = source.width as f64 * pixel_aspect;
+    let height = source.height as f64;
+    let factor = (target.width as f64 / width).min(target.height as f64 / height);
+    Viewport::centered(
+        ((width * factor).round() as u32).max(1),
+        ((height * factor).round() as u32).max(1),
+        target,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: PhysicalSize<u32> = PhysicalSize::new(8, 6);
+    const TARGET: PhysicalSize<u32> = PhysicalSize::new(64, 48);
+
+    fn fixed(mode: ScaleMode, scale: f64) -> Scaling {
+        Scaling {
+            mode,
+            scale: Some(scale),
+            ..Scaling::default()
+        }
+    }
+
+    #[test]
+    fn parses_scale_modes() {
+        for (name, mode) in [
+            ("integer", ScaleMode::Integer),
+            ("sharp-bilinear", ScaleMode::SharpBilinear),
+            ("coverage", ScaleMode::Coverage),
+        ] {
+            assert_eq!(name.parse::<ScaleMode>().unwrap(), mode);
+        }
+        for name in ["", "bilinear", "Integer"] {
+            assert!(name.parse::<ScaleMode>().is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn parses_scale2x_modes() {
+        assert_eq!("scale2x".parse::<ScaleMode>().unwrap(), ScaleMode::Scale2x);
+        assert_eq!("scale3x".parse::<ScaleMode>().unwrap(), ScaleMode::Scale3x);
+        assert!("scale4x".parse::<ScaleMode>().is_err());
+    }
+
+    #[test]
+    fn parses_xbrz_modes() {
+        assert_eq!("xbrz2".parse::<ScaleMode>().unwrap(), ScaleMode::Xbrz(2));
+        assert_eq!("xbrz6".parse::<ScaleMode>().unwrap(), ScaleMode::Xbrz(6));
+        for name in ["xbrz", "xbrz1", "xbrz7"] {
+            assert!(name.parse::<ScaleMode>().is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn parses_hqx_modes() {
+        assert_eq!("hq2x".parse::<ScaleMode>().unwrap(), ScaleMode::Hq2x);
+        for name in ["hqx", "hq3x", "hq4x"] {
+            assert!(name.parse::<ScaleMode>().is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn parses_pixel_aspects() {
+        assert_eq!(
+            "8:7".parse::<PixelAspect>().unwrap(),
+            PixelAspect(8.0 / 7.0)
+        );
+        assert_eq!(
+            " 4 : 3 ".parse::<PixelAspect>().unwrap(),
+            PixelAspect(4.0 / 3.0)
+        );
+        assert_eq!("1.5".parse::<PixelAspect>().unwrap(), PixelAspect(1.5));
+        for aspect in ["", "0", "-1", "1:0", "0:1", "nan", "inf", "a:b", "8:7:1"] {
+            assert!(aspect.parse::<PixelAspect>().is_err(), "{aspect}");
+        }
+    }
+
+    #[test]
+    fn picks_the_largest_integer_factors_close_to_the_pixel_aspect() {
+        assert_eq!(
+            integer_factors(SOURCE, 1.0, TARGET),
+            PhysicalSize::new(8, 8)
+        );
+        assert_eq!(
+            integer_factors(SOURCE, 8.0 / 7.0, TARGET),
+            PhysicalSize::new(8, 7)
+        );
+        // Neither 1 nor 2 is within the tolerance of 1.5, so the closer wins.
+        assert_eq!(
+            integer_factors(SOURCE, 1.5, PhysicalSize::new(16, 48)),
+            PhysicalSize::new(2, 1)
+        );
+    }
+
+    #[test]
+    fn never_picks_integer_factors_below_one() {
+        assert_eq!(
+            integer_factors(SOURCE, 1.0, PhysicalSize::new(4, 3)),
+            PhysicalSize::new(1, 1)
+        );
+        assert_eq!(
+            integer_factors(SOURCE, 8.0 / 7.0, PhysicalSize::new(0, 0)),
+            PhysicalSize::new(1, 1)
+        );
+    }
+
+    #[test]
+    fn snaps_to_the_closest_whole_factor_within_bounds() {
+        let scaling = Scaling::default();
+        let bounds = PhysicalSize::new(1000, 1000);
+        assert_eq!(
+            scaling.snapped_size(SOURCE, PhysicalSize::new(50, 40), bounds),
+            PhysicalSize::new(48, 36)
+        );
+        assert_eq!(
+            scaling.snapped_size(SOURCE, PhysicalSize::new(54, 41), bounds),
+            PhysicalSize::new(56, 42)
+        );
+        assert_eq!(
+            scaling.snapped_size(SOURCE, PhysicalSize::new(54, 41), PhysicalSize::new(20, 20)),
+            PhysicalSize::new(16, 12)
+        );
+        // A tall window limits the width of an image taller than wide.
+        assert_eq!(
+            scaling.snapped_size(
+                PhysicalSize::new(6, 8),
+                PhysicalSize::new(100, 1000),
+                bounds
+            ),
+            PhysicalSize::new(102, 136)
+        );
+        // A factor of 1 is allowed even when it exceeds the bounds.
+        assert_eq!(
+            scaling.snapped_size(SOURCE, PhysicalSize::new(54, 41), PhysicalSize::new(4, 4)),
+            SOURCE
+        );
+    }
+
+    #[test]
+    fn limits_scales_to_upscaler_passes_that_fit() {
+        let source = PhysicalSize::new(256, 224);
+        let max_scale = |mode, chain| {
+            let scaling = Scaling {
+                mode,
+                chain,
+                ..Scaling::default()
+            };
+            scaling.max_scale(source, TARGET, 1.0, 8192)
+        };
+        assert_eq!(max_scale(ScaleMode::Integer, false), Some(32.0));
+        assert_eq!(max_scale(ScaleMode::Scale3x, false), Some(32.0));
+        // A fourth Scale3x pass would be 81 times the image.
+        assert_eq!(max_scale(ScaleMode::Scale3x, true), Some(27.0));
+        // xBRZ passes of 6 and 6 would be 36 times the image.
+        assert_eq!(max_scale(ScaleMode::Xbrz(6), true), Some(30.0));
+
+        let wide = Scaling {
+            mode: ScaleMode::Scale3x,
+            ..Scaling::default()
+        };
+        assert_eq!(
+            wide.max_scale(PhysicalSize::new(2731, 10), TARGET, 1.0, 8192),
+            None
+        );
+        assert_eq!(
+            wide.max_scale(PhysicalSize::new(2730, 10), TARGET, 1.0, 8192),
+            Some(3.0)
+        );
+    }
+
+    #[test]
+    fn limits_scales_to_prescales_that_fit() {
+        let source = PhysicalSize::new(256, 224);
+        let pal = Scaling {
+            mode: ScaleMode::SharpBilinear,
+            pixel_aspect: PixelAspect(8.0 / 7.0),
+            ..Scaling::default()
+        };
+        // At 32, the image is 9362 wide and its prescale 9472.
+        assert_eq!(pal.max_scale(source, TARGET, 0.25, 8192), Some(28.0));
+        let fallback = Scaling {
+            mode: ScaleMode::Integer,
+            aspect_fallback: true,
+            ..pal
+        };
+        assert_eq!(fallback.max_scale(source, TARGET, 1.0, 8192), Some(28.0));
+
+        // At 3.25, the image is 975 wide but its prescale 1200.
+        let sharp = Scaling {
+            mode: ScaleMode::SharpBilinear,
+            ..Scaling::default()
+        };
+        assert_eq!(
+            sharp.max_scale(PhysicalSize::new(300, 10), TARGET, 0.25, 1000),
+            Some(3.0)
+        );
+    }
+
+    #[test]
+    fn saturates_huge_fixed_scales_instead_of_overflowing() {
+        for mode in [
+            ScaleMode::Integer,
+            ScaleMode::SharpBilinear,
+            ScaleMode::Coverage,
+            ScaleMode::Scale2x,
+            ScaleMode::Xbrz(4),
+        ] {
+            for chain in [false, true] {
+                let scaling = Scaling {
+                    chain,
+                    ..fixed(mode, 1e9)
+                };
+                let layout = scaling.layout(SOURCE, TARGET);
+                assert!(layout.viewport.width >= 1 << 30, "{mode:?}");
+                // Chained upscalers double up to the saturated factor.
+                for (_, size) in layout.passes(SOURCE) {
+                    assert!(size.width >= SOURCE.width, "{mode:?}");
+                }
+            }
+        }
+        let scaling = Scaling {
+            aspect_fallback: true,
+            pixel_aspect: PixelAspect(8.0 / 7.0),
+            ..fixed(ScaleMode::Integer, 1e9)
+        };
+        assert_eq!(scaling.layout(SOURCE, TARGET).viewport.height, u32::MAX);
+    }
+}
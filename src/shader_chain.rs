@@ -0,0 +1,756 @@
+use crate::{
+    blit::{self, Blit, BlitPipeline, ExtraBindings},
+    preset::{PassPreset, Preset, PresetError, WrapMode},
+    renderer,
+    scaling::Viewport,
+    slang::{self, SlangPass, SlangShader},
+    texture::{self, Texture},
+};
+use pollster::FutureExt;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use winit::dpi::PhysicalSize;
+
+/// Every pass shares the vertex stage and bindings of blit.wgsl, plus the
+/// pass information of chain.wgsl.
+const PRELUDE: &str = concat!(
+    include_str!("shaders/blit.wgsl"),
+    include_str!("shaders/chain.wgsl"),
+);
+
+/// Format of the sRGB-encoded copies around chains of slang passes.
+const ENCODED_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+const DECODED_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ShaderChainError {
+    #[error(transparent)]
+    Preset(#[from] PresetError),
+    #[error("Could not read shader {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("Could not compile shader {0}: {1}")]
+    Compile(PathBuf, String),
+    #[error("Could not load texture {0}: {1}")]
+    Texture(PathBuf, image::ImageError),
+}
+
+impl ShaderChainError {
+    /// File the error is about, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Preset(PresetError::Io(path, _))
+            | Self::Io(path, _)
+            | Self::Compile(path, _)
+            | Self::Texture(path, _) => Some(path),
+            Self::Preset(_) => None,
+        }
+    }
+}
+
+/// Named value a shader lets users tune, e.g. the strength of scanlines.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderParameter {
+    pub name: String,
+    pub description: String,
+    pub default: f32,
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+    /// Current value, the default unless the preset sets one.
+    pub value: f32,
+}
+
+impl ShaderParameter {
+    /// Digits after the decimal point that show every step.
+    pub fn decimals(&self) -> usize {
+        if self.step > 0.0 {
+            (-self.step.log10()).ceil().clamp(0.0, 6.0) as usize
+        } else {
+            2
+        }
+    }
+
+    /// The value `steps` steps away, rounded to [`ShaderParameter::decimals`]
+    /// so that repeated steps don't drift, and kept between the min and max.
+    pub fn stepped(&self, steps: i32) -> f32 {
+        let value = self.value + steps as f32 * self.step;
+        let scale = 10f32.powi(self.decimals() as i32);
+        ((value * scale).round() / scale)
+            .max(self.min)
+            .min(self.max)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct PassUniforms {
+    source_size: [f32; 4],
+    original_size: [f32; 4],
+    output_size: [f32; 4],
+    frame_count: u32,
+    _padding: [u32; 3],
+}
+
+/// Width, height and their reciprocals, as chain.wgsl takes sizes.
+fn size_with_reciprocal(size: PhysicalSize<u32>) -> [f32; 4] {
+    let (width, height) = (size.width as f32, size.height as f32);
+    [width, height, 1.0 / width, 1.0 / height]
+}
+
+/// A WGSL pass, read but not compiled yet.
+struct WgslShader {
+    path: PathBuf,
+    /// Source without its `#pragma parameter` lines.
+    source: String,
+    parameters: Vec<ShaderParameter>,
+}
+
+impl WgslShader {
+    fn load(path: &Path) -> Result<Self, ShaderChainError> {
+        let text =
+            fs::read_to_string(path).map_err(|e| ShaderChainError::Io(path.to_owned(), e))?;
+        let mut source = String::with_capacity(text.len());
+        let mut parameters = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            // Blanked rather than removed, so that errors keep their line numbers.
+            match line.trim().strip_prefix("#pragma parameter") {
+                Some(rest) => {
+                    parameters.push(slang::parse_parameter(rest.trim()).ok_or_else(|| {
+                        ShaderChainError::Compile(
+                            path.to_owned(),
+                            format!("{}:{}: invalid parameter", path.display(), number + 1),
+                        )
+                    })?)
+                }
+                None => source.push_str(line),
+            }
+            source.push('\n');
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            source,
+            parameters,
+        })
+    }
+}
+
+/// A pass written in WGSL after the prelude, see chain.wgsl.
+struct WgslPass {
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    uniforms: PassUniforms,
+    /// Index in the chain of every parameter the pass declares, in order.
+    parameters: Vec<usize>,
+    /// Draws the input of the pass, once laid out.
+    blit: Option<Blit>,
+}
+
+impl WgslPass {
+    fn new(
+        device: &wgpu::Device,
+        blit_pipeline: &BlitPipeline,
+        shader: &WgslShader,
+        format: wgpu::TextureFormat,
+        chain_parameters: &[ShaderParameter],
+    ) -> Result<Self, ShaderChainError> {
+        // The parameters follow the rest of `PassInfo` at the end of
+        // `PassUniforms`. Padding members rather than `@align` place them,
+        // which not every backend honours in uniform blocks.
+        let mut members = String::new();
+        if !shader.parameters.is_empty() {
+            members.push_str("    _padding0: u32,\n    _padding1: u32,\n    _padding2: u32,\n");
+        }
+        for parameter in &shader.parameters {
+            members.push_str(&format!("    {}: f32,\n", parameter.name));
+        }
+        let prelude = PRELUDE.replacen(
+            "    frame_count: u32,\n",
+            &format!("    frame_count: u32,\n{members}"),
+            1,
+        );
+        let parameters = shader
+            .parameters
+            .iter()
+            .map(|parameter| {
+                chain_parameters
+                    .iter()
+                    .position(|known| known.name == parameter.name)
+                    .expect("the chain has the parameters of every pass")
+            })
+            .collect::<Vec<_>>();
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader chain pass"),
+            source: wgpu::ShaderSource::Wgsl(format!("{prelude}{}", shader.source).into()),
+        });
+        let pipeline = blit_pipeline.create_pipeline_with_shader(
+            device,
+            &module,
+            format,
+            "fs_pass",
+            ExtraBindings::Parameters,
+        );
+        if let Some(error) = device.pop_error_scope().block_on() {
+            return Err(ShaderChainError::Compile(
+                shader.path.clone(),
+                error.to_string(),
+            ));
+        }
+        let parameters_size = (4 * parameters.len() as wgpu::BufferAddress).next_multiple_of(16);
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shader chain uniform buffer"),
+            size: std::mem::size_of::<PassUniforms>() as wgpu::BufferAddress + parameters_size,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Ok(Self {
+            pipeline,
+            uniform_buffer,
+            uniforms: bytemuck::Zeroable::zeroed(),
+            parameters,
+            blit: None,
+        })
+    }
+
+    fn write_uniforms(&self, queue: &wgpu::Queue, chain_parameters: &[ShaderParameter]) {
+        let mut data = bytemuck::bytes_of(&self.uniforms).to_vec();
+        for &i in &self.parameters {
+            data.extend_from_slice(&chain_parameters[i].value.to_ne_bytes());
+        }
+        data.resize(data.len().next_multiple_of(16), 0);
+        queue.write_buffer(&self.uniform_buffer, 0, &data);
+    }
+}
+
+/// A pass's shader, read but not compiled yet.
+enum ParsedShader {
+    Wgsl(WgslShader),
+    Slang(Box<SlangShader>),
+}
+
+impl ParsedShader {
+    fn parameters(&self) -> &[ShaderParameter] {
+        match self {
+            Self::Wgsl(wgsl) => &wgsl.parameters,
+            Self::Slang(slang) => &slang.parameters,
+        }
+    }
+}
+
+enum PassShader {
+    Wgsl(WgslPass),
+    Slang(SlangPass),
+}
+
+/// One compiled pass of a chain.
+struct ChainPass {
+    preset: PassPreset,
+    format: wgpu::TextureFormat,
+    sampler: wgpu::Sampler,
+    shader: PassShader,
+}
+
+/// Conversion of a texture between linear and sRGB-encoded colors.
+struct Conversion {
+    pipeline: wgpu::RenderPipeline,
+    format: wgpu::TextureFormat,
+    texture: Option<Texture>,
+    blit: Option<Blit>,
+}
+
+impl Conversion {
+    fn new(
+        device: &wgpu::Device,
+        blit_pipeline: &BlitPipeline,
+        format: wgpu::TextureFormat,
+        entry_point: &str,
+    ) -> Self {
+        Self {
+            pipeline: blit_pipeline.create_pipeline(
+                device,
+                format,
+                entry_point,
+                ExtraBindings::None,
+            ),
+            format,
+            texture: None,
+            blit: None,
+        }
+    }
+
+    fn set_layout(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        blit_pipeline: &BlitPipeline,
+        sampler: &wgpu::Sampler,
+        input: &Texture,
+    ) {
+        let size = input.size();
+        let texture = match self.texture.take() {
+            Some(texture) if texture.size() == size => texture,
+            _ => Texture::render_target(
+                device,
+                size,
+                self.format,
+                Some("Shader chain conversion texture"),
+            ),
+        };
+        let blit = Blit::new(device, blit_pipeline, &input.view, sampler);
+        blit.set_viewport(queue, Viewport::covering(size), size);
+        self.texture = Some(texture);
+        self.blit = Some(blit);
+    }
+
+    fn output(&self) -> &Texture {
+        self.texture
+            .as_ref()
+            .expect("conversions are laid out before their output is used")
+    }
+
+    fn render(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut render_pass = renderer::begin_pass(
+            encoder,
+            &self.output().view,
+            wgpu::Color::TRANSPARENT,
+            "Shader chain conversion pass",
+        );
+        self.blit
+            .as_ref()
+            .expect("conversions are laid out before they render")
+            .draw(&mut render_pass, &self.pipeline);
+    }
+}
+
+/// Passes described by a [`Preset`], each drawing the output of the previous
+/// one (or the source image) into a texture of its own.
+///
+/// Passes are WGSL, or RetroArch slang shaders for files ending in `.slang`.
+/// A chain with slang passes works on sRGB-encoded colors, like RetroArch:
+/// it encodes the source first and decodes its output last.
+pub struct ShaderChain {
+    passes: Vec<ChainPass>,
+    /// Encodes the source and decodes the output of chains with slang passes.
+    conversions: Option<[Conversion; 2]>,
+    /// Lookup textures of the preset, with their samplers.
+    lookup_textures: Vec<(Texture, wgpu::Sampler)>,
+    parameters: Vec<ShaderParameter>,
+    nearest_sampler: wgpu::Sampler,
+    /// Output of every pass, once laid out.
+    textures: Vec<Texture>,
+    frame_count: u32,
+    /// The preset and every file it loaded.
+    sources: Vec<PathBuf>,
+}
+
+impl ShaderChain {
+    /// Reads the preset at `path` and compiles its shaders.
+    pub fn load(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        blit_pipeline: &BlitPipeline,
+        path: &Path,
+    ) -> Result<Self, ShaderChainError> {
+        let preset = Preset::load(path)?;
+        let lookup_textures = preset
+            .textures
+            .iter()
+            .map(|lookup| {
+                let image = texture::load_image(&lookup.path)
+                    .map_err(|e| ShaderChainError::Texture(lookup.path.clone(), e))?;
+                // RetroArch doesn't decode lookup textures either.
+                let texture = Texture::from_image_with_format(
+                    device,
+                    queue,
+                    &image,
+                    wgpu::TextureFormat::Rgba8Unorm,
+                    Some("Lookup texture"),
+                );
+                let sampler = create_sampler(device, lookup.filter_linear, lookup.wrap_mode);
+                Ok((texture, sampler))
+            })
+            .collect::<Result<_, ShaderChainError>>()?;
+        let lookup_names: Vec<_> = preset.textures.iter().map(|t| t.name.as_str()).collect();
+
+        // Later passes and parameters are known before any pass is compiled.
+        let mut shaders = preset
+            .passes
+            .iter()
+            .map(|pass| {
+                let is_slang = pass
+                    .shader
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("slang"));
+                Ok(Some(if is_slang {
+                    ParsedShader::Slang(Box::new(SlangShader::load(&pass.shader)?))
+                } else {
+                    ParsedShader::Wgsl(WgslShader::load(&pass.shader)?)
+                }))
+            })
+            .collect::<Result<Vec<_>, ShaderChainError>>()?;
+        let mut sources = vec![path.to_owned()];
+        for shader in shaders.iter().flatten() {
+            match shader {
+                ParsedShader::Wgsl(wgsl) => sources.push(wgsl.path.clone()),
+                ParsedShader::Slang(slang) => sources.extend(slang.files.iter().cloned()),
+            }
+        }
+        sources.extend(preset.textures.iter().map(|lookup| lookup.path.clone()));
+        let aliases: Vec<_> = preset
+            .passes
+            .iter()
+            .zip(&shaders)
+            .map(|(pass, shader)| match shader {
+                Some(ParsedShader::Slang(slang)) => pass.alias.clone().or(slang.name.clone()),
+                _ => pass.alias.clone(),
+            })
+            .collect();
+        let mut parameters: Vec<ShaderParameter> = Vec::new();
+        for parameter in shaders.iter().flatten().flat_map(ParsedShader::parameters) {
+            if !parameters.iter().any(|known| known.name == parameter.name) {
+                parameters.push(parameter.clone());
+            }
+        }
+        for (name, value) in &preset.parameters {
+            match parameters
+                .iter_mut()
+                .find(|parameter| &parameter.name == name)
+            {
+                Some(parameter) => parameter.value = *value,
+                None => log::warn!("The preset sets unknown parameter \"{name}\""),
+            }
+        }
+
+        let mut passes = Vec::with_capacity(preset.passes.len());
+        for (i, pass) in preset.passes.into_iter().enumerate() {
+            let parsed = shaders[i].take().expect("every pass is compiled once");
+            let format = match &parsed {
+                _ if pass.float_framebuffer => wgpu::TextureFormat::Rgba16Float,
+                _ if pass.srgb_framebuffer => wgpu::TextureFormat::Rgba8UnormSrgb,
+                ParsedShader::Slang(slang) => {
+                    slang.format.unwrap_or(wgpu::TextureFormat::Rgba8Unorm)
+                }
+                ParsedShader::Wgsl(_) => wgpu::TextureFormat::Rgba8Unorm,
+            };
+            let shader = match parsed {
+                ParsedShader::Slang(slang) => PassShader::Slang(SlangPass::new(
+                    device,
+                    *slang,
+                    format,
+                    i,
+                    &aliases,
+                    &lookup_names,
+                    &parameters,
+                )?),
+                ParsedShader::Wgsl(wgsl) => PassShader::Wgsl(WgslPass::new(
+                    device,
+                    blit_pipeline,
+                    &wgsl,
+                    format,
+                    &parameters,
+                )?),
+            };
+            passes.push(ChainPass {
+                sampler: create_sampler(device, pass.filter_linear, pass.wrap_mode),
+                preset: pass,
+                format,
+                shader,
+            });
+        }
+        let conversions = passes
+            .iter()
+            .any(|pass| matches!(pass.shader, PassShader::Slang(_)))
+            .then(|| {
+                [
+                    Conversion::new(device, blit_pipeline, ENCODED_FORMAT, "fs_encode_srgb"),
+                    Conversion::new(device, blit_pipeline, DECODED_FORMAT, "fs_decode_srgb"),
+                ]
+            });
+
+        Ok(Self {
+            passes,
+            conversions,
+            lookup_textures,
+            parameters,
+            nearest_sampler: blit::create_sampler(device, wgpu::FilterMode::Nearest),
+            textures: Vec::new(),
+            frame_count: 0,
+            sources,
+        })
+    }
+
+    /// Sizes every pass for `source` drawn into a viewport of `viewport_size`,
+    /// keeping the textures when no size changed.
+    pub fn set_layout(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        blit_pipeline: &BlitPipeline,
+        source: &Texture,
+        viewport_size: PhysicalSize<u32>,
+    ) {
+        let original = match &mut self.conversions {
+            Some([encode, _]) => {
+                encode.set_layout(device, queue, blit_pipeline, &self.nearest_sampler, source);
+                encode.output()
+            }
+            None => source,
+        };
+
+        let max_size = device.limits().max_texture_dimension_2d;
+        let mut sizes = Vec::with_capacity(self.passes.len());
+        let mut input_size = original.size();
+        for (i, pass) in self.passes.iter_mut().enumerate() {
+            let requested = PhysicalSize::new(
+                pass.preset
+                    .scale_x
+                    .apply(input_size.width, viewport_size.width),
+                pass.preset
+                    .scale_y
+                    .apply(input_size.height, viewport_size.height),
+            );
+            // Rendering at a smaller scale than the preset asks for beats not
+            // rendering at all, but changes what the passes draw.
+            let size = PhysicalSize::new(
+                requested.width.min(max_size),
+                requested.height.min(max_size),
+            );
+            if size != requested {
+                log::warn!(
+                    "Pass {i} ({}) needs a {}x{} texture, larger than the device limit of \
+                     {max_size}, and renders at {}x{} instead",
+                    pass.preset.shader.display(),
+                    requested.width,
+                    requested.height,
+                    size.width,
+                    size.height,
+                );
+            }
+            if let PassShader::Wgsl(wgsl) = &mut pass.shader {
+                wgsl.uniforms.source_size = size_with_reciprocal(input_size);
+                wgsl.uniforms.original_size = size_with_reciprocal(original.size());
+                wgsl.uniforms.output_size = size_with_reciprocal(size);
+            }
+            sizes.push(size);
+            input_size = size;
+        }
+
+        let unchanged = self.textures.len() == sizes.len()
+            && self
+                .textures
+                .iter()
+                .zip(&sizes)
+                .all(|(texture, &size)| texture.size() == size);
+        if !unchanged {
+            self.textures = self
+                .passes
+                .iter()
+                .zip(&sizes)
+                .map(|(pass, &size)| {
+                    Texture::render_target(device, size, pass.format, Some("Shader chain texture"))
+                })
+                .collect();
+        }
+
+        // The source may have changed even when its size didn't.
+        for (i, pass) in self.passes.iter_mut().enumerate() {
+            let input = i.checked_sub(1).map_or(original, |i| &self.textures[i]);
+            let size = self.textures[i].size();
+            match &mut pass.shader {
+                PassShader::Wgsl(wgsl) => {
+                    let blit = Blit::new(device, blit_pipeline, &input.view, &pass.sampler)
+                        .with_parameters(device, blit_pipeline, &wgsl.uniform_buffer);
+                    blit.set_viewport(queue, Viewport::covering(size), size);
+                    wgsl.blit = Some(blit);
+                }
+                PassShader::Slang(slang) => slang.set_layout(
+                    device,
+                    &slang::Inputs {
+                        original,
+                        source: input,
+                        pass_outputs: &self.textures[..i],
+                        lookup_textures: &self.lookup_textures,
+                        sampler: &pass.sampler,
+                        output_size: size,
+                        viewport_size,
+                    },
+                ),
+            }
+        }
+
+        if let Some([_, decode]) = &mut self.conversions {
+            let last = self.textures.last().expect("presets have passes");
+            decode.set_layout(device, queue, blit_pipeline, &self.nearest_sampler, last);
+        }
+    }
+
+    /// Parameters of every pass, each once even if several passes declare it.
+    pub fn parameters(&self) -> &[ShaderParameter] {
+        &self.parameters
+    }
+
+    /// Sets the parameter at `index` of [`ShaderChain::parameters`], clamped
+    /// to its range, from the next frame on.
+    pub fn set_parameter(&mut self, index: usize, value: f32) {
+        let parameter = &mut self.parameters[index];
+        parameter.value = value.max(parameter.min).min(parameter.max);
+    }
+
+    /// Writes the values of the parameters that differ from their defaults
+    /// back to the preset the chain was loaded from.
+    pub fn save_parameters(&self) -> Result<(), PresetError> {
+        let changed: Vec<_> = self
+            .parameters
+            .iter()
+            .filter(|parameter| parameter.value != parameter.default)
+            .map(|parameter| (parameter.name.clone(), parameter.value))
+            .collect();
+        Preset::save_parameters(&self.sources[0], &changed)
+    }
+
+    /// The preset and every file it loaded, e.g. to reload the chain when one
+    /// changes.
+    pub fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+
+    /// Output of the last pass. Only valid after [`ShaderChain::set_layout`].
+    pub fn output(&self) -> &Texture {
+        match &self.conversions {
+            Some([_, decode]) => decode.output(),
+            None => self
+                .textures
+                .last()
+                .expect("shader chains are laid out before their output is used"),
+        }
+    }
+
+    pub fn render(&mut self, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder) {
+        if let Some([encode, _]) = &self.conversions {
+            encode.render(encoder);
+        }
+        for (pass, texture) in self.passes.iter_mut().zip(&self.textures) {
+            let frame_count = pass
+                .preset
+                .frame_count_mod
+                .map_or(self.frame_count, |modulus| self.frame_count % modulus);
+            match &mut pass.shader {
+                PassShader::Wgsl(wgsl) => {
+                    wgsl.uniforms.frame_count = frame_count;
+                    wgsl.write_uniforms(queue, &self.parameters);
+                    let mut render_pass = renderer::begin_pass(
+                        encoder,
+                        &texture.view,
+                        wgpu::Color::TRANSPARENT,
+                        "Shader chain pass",
+                    );
+                    wgsl.blit
+                        .as_ref()
+                        .expect("shader chains are laid out before they render")
+                        .draw(&mut render_pass, &wgsl.pipeline);
+                }
+                PassShader::Slang(slang) => {
+                    slang.render(queue, encoder, &texture.view, frame_count, &self.parameters)
+                }
+            }
+        }
+        if let Some([_, decode]) = &self.conversions {
+            decode.render(encoder);
+        }
+        self.frame_count = self.frame_count.wrapping_add(1);
+    }
+}
+
+fn create_sampler(
+    device: &wgpu::Device,
+    filter_linear: bool,
+    wrap_mode: WrapMode,
+) -> wgpu::Sampler {
+    let filter = if filter_linear {
+        wgpu::FilterMode::Linear
+    } else {
+        wgpu::FilterMode::Nearest
+    };
+    let address_mode = match wrap_mode {
+        WrapMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        WrapMode::ClampToBorder
+            if device
+                .features()
+                .contains(wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER) =>
+        {
+            wgpu::AddressMode::ClampToBorder
+        }
+        WrapMode::ClampToBorder => {
+            log::warn!("The device can't clamp to a border, clamping to the edge instead");
+            wgpu::AddressMode::ClampToEdge
+        }
+        WrapMode::Repeat => wgpu::AddressMode::Repeat,
+        WrapMode::MirroredRepeat => wgpu::AddressMode::MirrorRepeat,
+    };
+    blit::create_wrapping_sampler(device, filter, address_mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameter(min: f32, max: f32, step: f32, value: f32) -> ShaderParameter {
+        ShaderParameter {
+            name: "STRENGTH".to_owned(),
+            description: "Strength".to_owned(),
+            default: min,
+            min,
+            max,
+            step,
+            value,
+        }
+    }
+
+    #[test]
+    fn shows_enough_decimals_for_every_step() {
+        for (step, decimals) in [
+            (1.0, 0),
+            (5.0, 0),
+            (0.5, 1),
+            (0.1, 1),
+            (0.05, 2),
+            (0.01, 2),
+            (0.0001, 4),
+            (1e-9, 6),
+            (0.0, 2),
+        ] {
+            assert_eq!(
+                parameter(0.0, 1.0, step, 0.0).decimals(),
+                decimals,
+                "{step}"
+            );
+        }
+    }
+
+    #[test]
+    fn steps_without_drifting() {
+        let mut strength = parameter(0.0, 1.0, 0.1, 0.0);
+        for _ in 0..7 {
+            strength.value = strength.stepped(1);
+        }
+        assert_eq!(strength.value, 0.7);
+        assert_eq!(strength.stepped(-3), 0.4);
+    }
+
+    #[test]
+    fn steps_up_to_the_min_and_max() {
+        let strength = parameter(0.0, 1.0, 0.3, 0.9);
+        assert_eq!(strength.stepped(1), 1.0);
+        assert_eq!(strength.stepped(-4), 0.0);
+
+        let strength = parameter(-1.0, 1.0, 0.5, 1.0);
+        assert_eq!(strength.stepped(1), 1.0);
+        assert_eq!(strength.stepped(-1), 0.5);
+        assert_eq!(strength.stepped(-10), -1.0);
+        assert_eq!(strength.stepped(0), 1.0);
+    }
+}
@@ -0,0 +1,128 @@
+struct Uniforms {
+    // Destination rectangle in target pixels: x, y, width, height.
+    viewport: vec4<f32>,
+    target_size: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // Triangle strip covering the viewport rectangle.
+    let uv = vec2<f32>(f32(index & 1u), f32(index >> 1u));
+    let pixel = uniforms.viewport.xy + uv * uniforms.viewport.zw;
+    let ndc = pixel / uniforms.target_size * 2.0 - 1.0;
+    var out: VertexOutput;
+    out.position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@group(0) @binding(1)
+var source: texture_2d<f32>;
+@group(0) @binding(2)
+var source_sampler: sampler;
+
+// Loads a source texel, repeating the edge texels outside of the texture.
+fn load_clamped(texel: vec2<i32>) -> vec4<f32> {
+    let max_texel = vec2<i32>(textureDimensions(source)) - 1;
+    return textureLoad(source, clamp(texel, vec2<i32>(0), max_texel), 0);
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(source, source_sampler, in.uv);
+}
+
+// Colors are premultiplied from the first pass on, so that filtering never
+// weighs in the colors of transparent texels.
+@fragment
+fn fs_premultiply(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source, source_sampler, in.uv);
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+
+// Slang shaders take and give sRGB-encoded colors, as RetroArch stores them
+// in UNORM textures, where every other stage works on linear ones.
+@fragment
+fn fs_encode_srgb(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source, source_sampler, in.uv);
+    return vec4<f32>(linear_to_srgb(color.rgb), color.a);
+}
+
+@fragment
+fn fs_decode_srgb(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source, source_sampler, in.uv);
+    return vec4<f32>(srgb_to_linear(color.rgb), color.a);
+}
+
+// Upper bound on the source texels visited per axis when minifying.
+const MAX_COVERAGE_TAPS: i32 = 16;
+
+struct CoverageTap {
+    texel: i32,
+    weight: f32,
+}
+
+// Number of taps along one axis for a footprint from `lo` to `hi`.
+fn coverage_taps(lo: f32, hi: f32) -> i32 {
+    return min(i32(ceil(hi)) - i32(floor(lo)), MAX_COVERAGE_TAPS);
+}
+
+// The `i`th source texel along one axis under a footprint from `lo` to `hi`,
+// weighted by the length of the footprint it covers. Footprints over more
+// texels than there are taps are split into even parts instead, each standing
+// for the texel at its middle, so that the taps still span all of it.
+fn coverage_tap(lo: f32, hi: f32, i: i32) -> CoverageTap {
+    let first = i32(floor(lo));
+    if i32(ceil(hi)) - first <= MAX_COVERAGE_TAPS {
+        let texel = first + i;
+        return CoverageTap(texel, min(hi, f32(texel + 1)) - max(lo, f32(texel)));
+    }
+    let part = (hi - lo) / f32(MAX_COVERAGE_TAPS);
+    return CoverageTap(i32(floor(lo + (f32(i) + 0.5) * part)), part);
+}
+
+// Averages the source texels under this output pixel, weighted by the area of
+// the pixel's footprint that each of them covers.
+@fragment
+fn fs_coverage(in: VertexOutput) -> @location(0) vec4<f32> {
+    let source_size = vec2<f32>(textureDimensions(source));
+    let footprint = source_size / uniforms.viewport.zw;
+    let center = in.uv * source_size;
+    let lo = center - footprint * 0.5;
+    let hi = center + footprint * 0.5;
+    let taps = vec2<i32>(coverage_taps(lo.x, hi.x), coverage_taps(lo.y, hi.y));
+
+    var sum = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var y = 0; y < taps.y; y += 1) {
+        let tap_y = coverage_tap(lo.y, hi.y, y);
+        for (var x = 0; x < taps.x; x += 1) {
+            let tap_x = coverage_tap(lo.x, hi.x, x);
+            let weight = tap_x.weight * tap_y.weight;
+            sum += load_clamped(vec2<i32>(tap_x.texel, tap_y.texel)) * weight;
+            total += weight;
+        }
+    }
+    return sum / total;
+}
+
@@ -0,0 +1,19 @@
+// Prelude of shader chain passes, after blit.wgsl. A pass draws `source`, the
+// output of the previous pass or the original image for the first one, with
+// a fragment entry point named fs_pass.
+
+struct PassInfo {
+    // Width, height, 1 / width and 1 / height of each image, in pixels.
+    source_size: vec4<f32>,
+    original_size: vec4<f32>,
+    output_size: vec4<f32>,
+    // Frames drawn since the chain was loaded.
+    frame_count: u32,
+    // Then an f32 for every parameter the pass declares with a line like
+    // `#pragma parameter STRENGTH "Strength" 0.5 0.0 1.0 0.05` (name,
+    // description, default, minimum, maximum and optional step), named after
+    // it: `pass_info.STRENGTH`.
+}
+
+@group(1) @binding(0)
+var<uniform> pass_info: PassInfo;
@@ -0,0 +1,19 @@
+// Checkerboard drawn into the rectangle of the scaled image before it, for
+// its transparent parts to show against.
+
+struct CheckerboardUniforms {
+    // Premultiplied linear colors of the squares, the top-left one first.
+    colors: array<vec4<f32>, 2>,
+    // Size of a square in target pixels.
+    square_size: vec2<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> checkerboard: CheckerboardUniforms;
+
+@fragment
+fn fs_checkerboard(in: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = in.position.xy - uniforms.viewport.xy;
+    let square = vec2<i32>(floor(offset / checkerboard.square_size));
+    return checkerboard.colors[(square.x + square.y) & 1];
+}
@@ -0,0 +1,107 @@
+// CRT emulation, drawn over the whole target from the scaled image already
+// rendered into `source` at the same size.
+
+struct CrtUniforms {
+    // Rectangle of the scaled image in target pixels: x, y, width, height.
+    image: vec4<f32>,
+    background: vec4<f32>,
+    source_size: vec2<f32>,
+    scanlines: f32,
+    mask: u32,
+    mask_strength: f32,
+    curvature: f32,
+    bloom: f32,
+    vignette: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> crt: CrtUniforms;
+
+fn crt_warp(uv: vec2<f32>) -> vec2<f32> {
+    let centered = uv * 2.0 - 1.0;
+    let warped = centered * (1.0 + crt.curvature * centered.yx * centered.yx);
+    return warped * 0.5 + 0.5;
+}
+
+fn crt_sample(uv: vec2<f32>) -> vec4<f32> {
+    let pixel = crt.image.xy + uv * crt.image.zw;
+    return textureSampleLevel(source, source_sampler, pixel / uniforms.target_size, 0.0);
+}
+
+// Brightness of the beam at `row`, in source rows, for scanlines `rows`
+// target rows tall. Every source row is a scanline wherever it lands on the
+// target, so they never drift across rows at fractional scales. The beam is
+// centred above the middle of the row, so that with as few as two target rows
+// one is lit and the other is the gap.
+fn crt_beam(row: f32, rows: f32) -> f32 {
+    let distance = fract(row) - (0.5 - 0.5 / max(rows, 1.0));
+    return exp(-8.0 * distance * distance);
+}
+
+// Phosphors lit for a target pixel, in whole target pixels so the pattern
+// never aliases.
+fn crt_mask(pixel: vec2<u32>) -> vec3<f32> {
+    var channel = pixel.x % 3u;
+    var lit = true;
+    // Numbered as crt::Mask.
+    switch crt.mask {
+        // Aperture grille.
+        case 1u: {}
+        // Slot mask.
+        case 2u: {
+            // Triads alternate between two rows of slots, offset by half.
+            lit = (pixel.y + (pixel.x / 3u) % 2u * 2u) % 4u != 3u;
+        }
+        // Shadow mask.
+        case 3u: {
+            channel = (pixel.x + pixel.y % 2u * 2u) % 3u;
+        }
+        default: {
+            return vec3<f32>(1.0);
+        }
+    }
+    let dim = 1.0 - crt.mask_strength;
+    var mask = vec3<f32>(dim);
+    mask[channel] = 1.0;
+    return select(vec3<f32>(dim), mask, lit);
+}
+
+@fragment
+fn fs_crt(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = (in.position.xy - crt.image.xy) / crt.image.zw;
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+        return textureLoad(source, vec2<i32>(in.position.xy), 0);
+    }
+    let warped = crt_warp(uv);
+    if any(warped < vec2<f32>(0.0)) || any(warped > vec2<f32>(1.0)) {
+        return crt.background;
+    }
+
+    let base = crt_sample(warped);
+    let beam = crt_beam(warped.y * crt.source_size.y, crt.image.w / crt.source_size.y);
+    var color = base.rgb * mix(1.0, beam, crt.scanlines) * crt_mask(vec2<u32>(in.position.xy));
+    var alpha = base.a;
+
+    if crt.bloom > 0.0 {
+        // Light scattered from the neighbouring source pixels, which also
+        // fills in the scanline gaps and mask around bright areas.
+        let step = 1.0 / crt.source_size;
+        var glow = vec4<f32>(0.0);
+        for (var y = -1; y <= 1; y++) {
+            for (var x = -1; x <= 1; x++) {
+                let weight = f32((2 - abs(x)) * (2 - abs(y))) / 16.0;
+                glow += crt_sample(warped + vec2<f32>(f32(x), f32(y)) * step) * weight;
+            }
+        }
+        color += glow.rgb * crt.bloom;
+        // The glow covers transparent pixels too, as much as it lights them.
+        alpha = max(alpha, min(glow.a * crt.bloom, 1.0));
+    }
+
+    let edge = warped * (1.0 - warped);
+    let vignette = pow(16.0 * edge.x * edge.y, 0.25);
+    color *= mix(1.0, vignette, crt.vignette);
+
+    // Premultiplied colors can't be brighter than they are opaque.
+    return vec4<f32>(min(color, vec3<f32>(alpha)), alpha);
+}
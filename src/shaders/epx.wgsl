@@ -0,0 +1,81 @@
+// AdvMAME Scale2x and Scale3x. These are drawn into a texture exactly 2 or 3
+// times the size of the source, so every fragment maps to one sub-pixel of a
+// source pixel.
+
+fn same(a: vec4<f32>, b: vec4<f32>) -> bool {
+    return all(a == b);
+}
+
+@fragment
+fn fs_scale2x(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(floor(in.position.xy));
+    let center = pixel / 2;
+    // Step towards the quadrant this sub-pixel lies in, which turns the
+    // top-left rule of EPX into the rule for every quadrant.
+    let step = (pixel % 2) * 2 - 1;
+    let e = load_clamped(center);
+    let horizontal = load_clamped(center + vec2<i32>(step.x, 0));
+    let vertical = load_clamped(center + vec2<i32>(0, step.y));
+    let opposite_horizontal = load_clamped(center - vec2<i32>(step.x, 0));
+    let opposite_vertical = load_clamped(center - vec2<i32>(0, step.y));
+
+    if same(horizontal, vertical)
+        && !same(horizontal, opposite_vertical)
+        && !same(vertical, opposite_horizontal) {
+        return vertical;
+    }
+    return e;
+}
+
+@fragment
+fn fs_scale3x(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(floor(in.position.xy));
+    let center = pixel / 3;
+    let sub = pixel % 3;
+    let a = load_clamped(center + vec2<i32>(-1, -1));
+    let b = load_clamped(center + vec2<i32>(0, -1));
+    let c = load_clamped(center + vec2<i32>(1, -1));
+    let d = load_clamped(center + vec2<i32>(-1, 0));
+    let e = load_clamped(center);
+    let f = load_clamped(center + vec2<i32>(1, 0));
+    let g = load_clamped(center + vec2<i32>(-1, 1));
+    let h = load_clamped(center + vec2<i32>(0, 1));
+    let i = load_clamped(center + vec2<i32>(1, 1));
+
+    if same(b, h) || same(d, f) {
+        return e;
+    }
+    switch sub.y * 3 + sub.x {
+        case 0: {
+            return select(e, d, same(d, b));
+        }
+        case 1: {
+            let edge = (same(d, b) && !same(e, c)) || (same(b, f) && !same(e, a));
+            return select(e, b, edge);
+        }
+        case 2: {
+            return select(e, f, same(b, f));
+        }
+        case 3: {
+            let edge = (same(d, b) && !same(e, g)) || (same(d, h) && !same(e, a));
+            return select(e, d, edge);
+        }
+        case 5: {
+            let edge = (same(b, f) && !same(e, i)) || (same(h, f) && !same(e, c));
+            return select(e, f, edge);
+        }
+        case 6: {
+            return select(e, d, same(d, h));
+        }
+        case 7: {
+            let edge = (same(d, h) && !same(e, i)) || (same(h, f) && !same(e, g));
+            return select(e, h, edge);
+        }
+        case 8: {
+            return select(e, f, same(h, f));
+        }
+        default: {
+            return e;
+        }
+    }
+}
@@ -0,0 +1,60 @@
+// hq2x by Maxim Stepin, with the blend for every pattern read from a lookup
+// table built by hqx.rs. Drawn into a texture twice the size of the source.
+
+@group(1) @binding(0)
+var hqx_lookup_table: texture_2d<f32>;
+
+fn hqx_yuv(color: vec3<f32>) -> vec3<f32> {
+    let srgb = linear_to_srgb(color);
+    return vec3<f32>(
+        dot(srgb, vec3<f32>(0.299, 0.587, 0.114)),
+        dot(srgb, vec3<f32>(-0.169, -0.331, 0.5)),
+        dot(srgb, vec3<f32>(0.5, -0.419, -0.081)),
+    );
+}
+
+// The classic thresholds of 48, 7 and 6 out of 255 on Y, U and V, plus the
+// luma one on alpha.
+fn hqx_differs(a: vec4<f32>, b: vec4<f32>) -> bool {
+    let threshold = vec4<f32>(48.0, 7.0, 6.0, 48.0) / 255.0;
+    let difference = abs(vec4<f32>(hqx_yuv(a.rgb) - hqx_yuv(b.rgb), a.a - b.a));
+    return any(difference > threshold);
+}
+
+@fragment
+fn fs_hqx(in: VertexOutput) -> @location(0) vec4<f32> {
+    let source_size = vec2<f32>(textureDimensions(source));
+    let texel = in.uv * source_size;
+    let c = vec2<i32>(floor(texel));
+    let sub = min(vec2<i32>(fract(texel) * 2.0), vec2<i32>(1));
+
+    var w: array<vec4<f32>, 9>;
+    for (var i = 0; i < 9; i++) {
+        w[i] = load_clamped(c + vec2<i32>(i % 3 - 1, i / 3 - 1));
+    }
+
+    var pattern = 0;
+    var bit = 0;
+    for (var i = 0; i < 9; i++) {
+        if i == 4 {
+            continue;
+        }
+        if hqx_differs(w[4], w[i]) {
+            pattern |= 1 << u32(bit);
+        }
+        bit++;
+    }
+    let cross = select(0, 1, hqx_differs(w[1], w[5]))
+        | select(0, 2, hqx_differs(w[5], w[7]))
+        | select(0, 4, hqx_differs(w[7], w[3]))
+        | select(0, 8, hqx_differs(w[3], w[1]));
+
+    let quadrant = sub * 2 - 1;
+    let horizontal = load_clamped(c + vec2<i32>(quadrant.x, 0));
+    let vertical = load_clamped(c + vec2<i32>(0, quadrant.y));
+    let corner = load_clamped(c + quadrant);
+
+    let weights = textureLoad(hqx_lookup_table, vec2<i32>(pattern, cross) * 2 + sub, 0);
+    let color = w[4] * weights.x + horizontal * weights.y + vertical * weights.z + corner * weights.w;
+    return color / dot(weights, vec4<f32>(1.0));
+}
@@ -0,0 +1,54 @@
+// Handheld LCD emulation, drawn over the whole target from the scaled image
+// already rendered into `source` at the same size, into one of two history
+// textures that alternate every frame.
+
+struct LcdUniforms {
+    // Rectangle of the scaled image in target pixels: x, y, width, height.
+    image: vec4<f32>,
+    source_size: vec2<f32>,
+    grid: f32,
+    subpixels: f32,
+    // How much of the previous frame is left, already scaled by frame time.
+    history_weight: f32,
+}
+
+@group(1) @binding(0)
+var<uniform> lcd: LcdUniforms;
+@group(1) @binding(1)
+var lcd_history: texture_2d<f32>;
+
+// Index of the source pixel covering a target pixel column or row.
+fn lcd_cell(pixel: vec2<f32>) -> vec2<f32> {
+    return floor((pixel - lcd.image.xy) * lcd.source_size / lcd.image.zw);
+}
+
+@fragment
+fn fs_lcd(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = vec2<i32>(in.position.xy);
+    let color = textureLoad(source, texel, 0);
+    let uv = (in.position.xy - lcd.image.xy) / lcd.image.zw;
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+        return color;
+    }
+
+    // The grid takes the last target row and column of every source pixel,
+    // in whole target pixels so it never aliases, and only when there is at
+    // least one other row or column left to light.
+    let factor = lcd.image.zw / lcd.source_size;
+    let pixel = floor(in.position.xy);
+    let last = lcd_cell(pixel + 0.5) != lcd_cell(pixel + 1.5);
+    let on_grid = any(last & factor >= vec2<f32>(2.0));
+    var rgb = color.rgb * select(1.0, 1.0 - lcd.grid, on_grid);
+
+    // Red, green and blue subpixels side by side, once there is room for them.
+    if factor.x >= 3.0 {
+        let cell = fract(uv.x * lcd.source_size.x);
+        let channel = u32(cell * 3.0);
+        var subpixel = vec3<f32>(1.0 - lcd.subpixels);
+        subpixel[channel] = 1.0;
+        rgb *= subpixel;
+    }
+
+    let previous = textureLoad(lcd_history, texel, 0);
+    return mix(vec4<f32>(rgb, color.a), previous, lcd.history_weight);
+}
@@ -0,0 +1,34 @@
+// Lines between the source pixels of the scaled image, and between tiles of
+// them, drawn over it.
+
+struct PixelGridUniforms {
+    // Premultiplied linear colors of the lines.
+    pixel_color: vec4<f32>,
+    tile_color: vec4<f32>,
+    // Size of a source pixel in target pixels.
+    pixel_size: vec2<f32>,
+    // Size of a tile in source pixels, 0 without tiles.
+    tile_size: vec2<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> pixel_grid: PixelGridUniforms;
+
+@fragment
+fn fs_pixel_grid(in: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = floor(in.position.xy - uniforms.viewport.xy);
+    // Lines take the first target pixel of every source pixel, but those on
+    // the edges of the image.
+    let pixel = floor((offset + 0.5) / pixel_grid.pixel_size);
+    let previous = floor((offset - 0.5) / pixel_grid.pixel_size);
+    let line = (pixel != previous) & (offset > vec2<f32>(0.0));
+    let tile_size = pixel_grid.tile_size;
+    let tile = (tile_size > vec2<f32>(0.0)) & (pixel % tile_size == vec2<f32>(0.0));
+    if any(line & tile) {
+        return pixel_grid.tile_color;
+    }
+    if any(line) {
+        return pixel_grid.pixel_color;
+    }
+    discard;
+}
@@ -0,0 +1,203 @@
+// xBRZ by Zenju, evaluated per output pixel so one pass works at any factor.
+// Drawn into a texture a whole multiple of the source size; the multiple is
+// recovered from the viewport size.
+
+const XBRZ_BLEND_NONE: i32 = 0;
+const XBRZ_BLEND_NORMAL: i32 = 1;
+const XBRZ_BLEND_DOMINANT: i32 = 2;
+const XBRZ_LUMINANCE_WEIGHT: f32 = 1.0;
+const XBRZ_EQUAL_COLOR_TOLERANCE: f32 = 0.11764706; // 30 / 255
+const XBRZ_STEEP_DIRECTION_THRESHOLD: f32 = 2.2;
+const XBRZ_DOMINANT_DIRECTION_THRESHOLD: f32 = 3.6;
+
+// Perceptual distance in YCbCr space, measured on gamma-encoded colors as
+// the thresholds above expect.
+fn xbrz_dist(a: vec4<f32>, b: vec4<f32>) -> f32 {
+    let w = vec3<f32>(0.2627, 0.6780, 0.0593);
+    let scale_b = 0.5 / (1.0 - w.b);
+    let scale_r = 0.5 / (1.0 - w.r);
+    let diff = linear_to_srgb(a.rgb) - linear_to_srgb(b.rgb);
+    let y = dot(diff, w) * XBRZ_LUMINANCE_WEIGHT;
+    let cb = scale_b * (diff.b - y);
+    let cr = scale_r * (diff.r - y);
+    return sqrt(y * y + cb * cb + cr * cr);
+}
+
+fn xbrz_similar(a: vec4<f32>, b: vec4<f32>) -> bool {
+    return xbrz_dist(a, b) < XBRZ_EQUAL_COLOR_TOLERANCE;
+}
+
+fn xbrz_eq(a: vec4<f32>, b: vec4<f32>) -> bool {
+    return all(a == b);
+}
+
+// How much of the pixel at `center` lies on the far side of the blend line.
+fn xbrz_left_ratio(center: vec2<f32>, origin: vec2<f32>, direction: vec2<f32>, scale: vec2<f32>) -> f32 {
+    let p0 = center - origin;
+    let projection = direction * (dot(p0, direction) / dot(direction, direction));
+    let distance = p0 - projection;
+    let orthogonal = vec2<f32>(-direction.y, direction.x);
+    let side = sign(dot(p0, orthogonal));
+    let v = side * length(distance * scale);
+    return smoothstep(-0.70710678, 0.70710678, v);
+}
+
+@fragment
+fn fs_xbrz(in: VertexOutput) -> @location(0) vec4<f32> {
+    let source_size = vec2<f32>(textureDimensions(source));
+    let scale = uniforms.viewport.zw / source_size;
+    let texel = in.uv * source_size;
+    let pos = fract(texel) - 0.5;
+    let c = vec2<i32>(floor(texel));
+
+    // Input pixel mapping:  -|x|x|x|-
+    //                       x|A|B|C|x
+    //                       x|D|E|F|x
+    //                       x|G|H|I|x
+    //                       -|x|x|x|-
+    let a = load_clamped(c + vec2<i32>(-1, -1));
+    let b = load_clamped(c + vec2<i32>(0, -1));
+    let cc = load_clamped(c + vec2<i32>(1, -1));
+    let d = load_clamped(c + vec2<i32>(-1, 0));
+    let e = load_clamped(c);
+    let f = load_clamped(c + vec2<i32>(1, 0));
+    let g = load_clamped(c + vec2<i32>(-1, 1));
+    let h = load_clamped(c + vec2<i32>(0, 1));
+    let i = load_clamped(c + vec2<i32>(1, 1));
+
+    // Blend results per corner: x = top-left, y = top-right, z = bottom-right,
+    // w = bottom-left.
+    var blend = vec4<i32>(XBRZ_BLEND_NONE);
+
+    if !((xbrz_eq(e, f) && xbrz_eq(h, i)) || (xbrz_eq(e, h) && xbrz_eq(f, i))) {
+        let dist_h_f = xbrz_dist(g, e) + xbrz_dist(e, cc) + xbrz_dist(load_clamped(c + vec2<i32>(0, 2)), i)
+            + xbrz_dist(i, load_clamped(c + vec2<i32>(2, 0))) + 4.0 * xbrz_dist(h, f);
+        let dist_e_i = xbrz_dist(d, h) + xbrz_dist(h, load_clamped(c + vec2<i32>(1, 2))) + xbrz_dist(b, f)
+            + xbrz_dist(f, load_clamped(c + vec2<i32>(2, 1))) + 4.0 * xbrz_dist(e, i);
+        let dominant = XBRZ_DOMINANT_DIRECTION_THRESHOLD * dist_h_f < dist_e_i;
+        if dist_h_f < dist_e_i && !xbrz_eq(e, f) && !xbrz_eq(e, h) {
+            blend.z = select(XBRZ_BLEND_NORMAL, XBRZ_BLEND_DOMINANT, dominant);
+        }
+    }
+
+    if !((xbrz_eq(d, e) && xbrz_eq(g, h)) || (xbrz_eq(d, g) && xbrz_eq(e, h))) {
+        let dist_g_e = xbrz_dist(load_clamped(c + vec2<i32>(-2, 1)), d) + xbrz_dist(d, b)
+            + xbrz_dist(load_clamped(c + vec2<i32>(-1, 2)), h) + xbrz_dist(h, f) + 4.0 * xbrz_dist(g, e);
+        let dist_d_h = xbrz_dist(load_clamped(c + vec2<i32>(-2, 0)), g) + xbrz_dist(g, load_clamped(c + vec2<i32>(0, 2)))
+            + xbrz_dist(a, e) + xbrz_dist(e, i) + 4.0 * xbrz_dist(d, h);
+        let dominant = XBRZ_DOMINANT_DIRECTION_THRESHOLD * dist_d_h < dist_g_e;
+        if dist_g_e > dist_d_h && !xbrz_eq(e, d) && !xbrz_eq(e, h) {
+            blend.w = select(XBRZ_BLEND_NORMAL, XBRZ_BLEND_DOMINANT, dominant);
+        }
+    }
+
+    if !((xbrz_eq(b, cc) && xbrz_eq(e, f)) || (xbrz_eq(b, e) && xbrz_eq(cc, f))) {
+        let dist_e_c = xbrz_dist(d, b) + xbrz_dist(b, load_clamped(c + vec2<i32>(1, -2))) + xbrz_dist(h, f)
+            + xbrz_dist(f, load_clamped(c + vec2<i32>(2, -1))) + 4.0 * xbrz_dist(e, cc);
+        let dist_b_f = xbrz_dist(a, e) + xbrz_dist(e, i) + xbrz_dist(load_clamped(c + vec2<i32>(0, -2)), cc)
+            + xbrz_dist(cc, load_clamped(c + vec2<i32>(2, 0))) + 4.0 * xbrz_dist(b, f);
+        let dominant = XBRZ_DOMINANT_DIRECTION_THRESHOLD * dist_b_f < dist_e_c;
+        if dist_e_c > dist_b_f && !xbrz_eq(e, b) && !xbrz_eq(e, f) {
+            blend.y = select(XBRZ_BLEND_NORMAL, XBRZ_BLEND_DOMINANT, dominant);
+        }
+    }
+
+    if !((xbrz_eq(a, b) && xbrz_eq(d, e)) || (xbrz_eq(a, d) && xbrz_eq(b, e))) {
+        let dist_d_b = xbrz_dist(load_clamped(c + vec2<i32>(-2, 0)), a) + xbrz_dist(a, load_clamped(c + vec2<i32>(0, -2)))
+            + xbrz_dist(g, e) + xbrz_dist(e, cc) + 4.0 * xbrz_dist(d, b);
+        let dist_a_e = xbrz_dist(load_clamped(c + vec2<i32>(-2, -1)), d) + xbrz_dist(d, h)
+            + xbrz_dist(load_clamped(c + vec2<i32>(-1, -2)), b) + xbrz_dist(b, f) + 4.0 * xbrz_dist(a, e);
+        let dominant = XBRZ_DOMINANT_DIRECTION_THRESHOLD * dist_d_b < dist_a_e;
+        if dist_d_b < dist_a_e && !xbrz_eq(e, d) && !xbrz_eq(e, b) {
+            blend.x = select(XBRZ_BLEND_NORMAL, XBRZ_BLEND_DOMINANT, dominant);
+        }
+    }
+
+    var result = e;
+
+    if blend.z != XBRZ_BLEND_NONE {
+        let dist_f_g = xbrz_dist(f, g);
+        let dist_h_c = xbrz_dist(h, cc);
+        let line = blend.z == XBRZ_BLEND_DOMINANT
+            || !((blend.y != XBRZ_BLEND_NONE && !xbrz_similar(e, g))
+                || (blend.w != XBRZ_BLEND_NONE && !xbrz_similar(e, cc))
+                || (xbrz_similar(g, h) && xbrz_similar(h, i) && xbrz_similar(i, f)
+                    && xbrz_similar(f, cc) && !xbrz_similar(e, i)));
+        var origin = vec2<f32>(0.0, 0.70710678);
+        var direction = vec2<f32>(1.0, -1.0);
+        if line {
+            let shallow = XBRZ_STEEP_DIRECTION_THRESHOLD * dist_f_g <= dist_h_c && !xbrz_eq(e, g) && !xbrz_eq(d, g);
+            let steep = XBRZ_STEEP_DIRECTION_THRESHOLD * dist_h_c <= dist_f_g && !xbrz_eq(e, cc) && !xbrz_eq(b, cc);
+            origin = select(vec2<f32>(0.0, 0.5), vec2<f32>(0.0, 0.25), shallow);
+            direction.x += select(0.0, 1.0, shallow);
+            direction.y -= select(0.0, 1.0, steep);
+        }
+        let blend_pixel = select(h, f, xbrz_dist(e, f) <= xbrz_dist(e, h));
+        result = mix(result, blend_pixel, xbrz_left_ratio(pos, origin, direction, scale));
+    }
+
+    if blend.w != XBRZ_BLEND_NONE {
+        let dist_h_a = xbrz_dist(h, a);
+        let dist_d_i = xbrz_dist(d, i);
+        let line = blend.w == XBRZ_BLEND_DOMINANT
+            || !((blend.z != XBRZ_BLEND_NONE && !xbrz_similar(e, a))
+                || (blend.x != XBRZ_BLEND_NONE && !xbrz_similar(e, i))
+                || (xbrz_similar(a, d) && xbrz_similar(d, g) && xbrz_similar(g, h)
+                    && xbrz_similar(h, i) && !xbrz_similar(e, g)));
+        var origin = vec2<f32>(-0.70710678, 0.0);
+        var direction = vec2<f32>(1.0, 1.0);
+        if line {
+            let shallow = XBRZ_STEEP_DIRECTION_THRESHOLD * dist_h_a <= dist_d_i && !xbrz_eq(e, a) && !xbrz_eq(b, a);
+            let steep = XBRZ_STEEP_DIRECTION_THRESHOLD * dist_d_i <= dist_h_a && !xbrz_eq(e, i) && !xbrz_eq(f, i);
+            origin = select(vec2<f32>(-0.5, 0.0), vec2<f32>(-0.25, 0.0), shallow);
+            direction.y += select(0.0, 1.0, shallow);
+            direction.x += select(0.0, 1.0, steep);
+        }
+        let blend_pixel = select(h, d, xbrz_dist(e, d) <= xbrz_dist(e, h));
+        result = mix(result, blend_pixel, xbrz_left_ratio(pos, origin, direction, scale));
+    }
+
+    if blend.y != XBRZ_BLEND_NONE {
+        let dist_b_i = xbrz_dist(b, i);
+        let dist_f_a = xbrz_dist(f, a);
+        let line = blend.y == XBRZ_BLEND_DOMINANT
+            || !((blend.x != XBRZ_BLEND_NONE && !xbrz_similar(e, i))
+                || (blend.z != XBRZ_BLEND_NONE && !xbrz_similar(e, a))
+                || (xbrz_similar(i, f) && xbrz_similar(f, cc) && xbrz_similar(cc, b)
+                    && xbrz_similar(b, a) && !xbrz_similar(e, cc)));
+        var origin = vec2<f32>(0.70710678, 0.0);
+        var direction = vec2<f32>(-1.0, -1.0);
+        if line {
+            let shallow = XBRZ_STEEP_DIRECTION_THRESHOLD * dist_b_i <= dist_f_a && !xbrz_eq(e, i) && !xbrz_eq(h, i);
+            let steep = XBRZ_STEEP_DIRECTION_THRESHOLD * dist_f_a <= dist_b_i && !xbrz_eq(e, a) && !xbrz_eq(d, a);
+            origin = select(vec2<f32>(0.5, 0.0), vec2<f32>(0.25, 0.0), shallow);
+            direction.y -= select(0.0, 1.0, shallow);
+            direction.x -= select(0.0, 1.0, steep);
+        }
+        let blend_pixel = select(f, b, xbrz_dist(e, b) <= xbrz_dist(e, f));
+        result = mix(result, blend_pixel, xbrz_left_ratio(pos, origin, direction, scale));
+    }
+
+    if blend.x != XBRZ_BLEND_NONE {
+        let dist_d_c = xbrz_dist(d, cc);
+        let dist_b_g = xbrz_dist(b, g);
+        let line = blend.x == XBRZ_BLEND_DOMINANT
+            || !((blend.w != XBRZ_BLEND_NONE && !xbrz_similar(e, cc))
+                || (blend.y != XBRZ_BLEND_NONE && !xbrz_similar(e, g))
+                || (xbrz_similar(cc, b) && xbrz_similar(b, a) && xbrz_similar(a, d)
+                    && xbrz_similar(d, g) && !xbrz_similar(e, a)));
+        var origin = vec2<f32>(0.0, -0.70710678);
+        var direction = vec2<f32>(-1.0, 1.0);
+        if line {
+            let shallow = XBRZ_STEEP_DIRECTION_THRESHOLD * dist_d_c <= dist_b_g && !xbrz_eq(e, cc) && !xbrz_eq(f, cc);
+            let steep = XBRZ_STEEP_DIRECTION_THRESHOLD * dist_b_g <= dist_d_c && !xbrz_eq(e, g) && !xbrz_eq(h, g);
+            origin = select(vec2<f32>(0.0, -0.5), vec2<f32>(0.0, -0.25), shallow);
+            direction.x -= select(0.0, 1.0, shallow);
+            direction.y += select(0.0, 1.0, steep);
+        }
+        let blend_pixel = select(d, b, xbrz_dist(e, b) <= xbrz_dist(e, d));
+        result = mix(result, blend_pixel, xbrz_left_ratio(pos, origin, direction, scale));
+    }
+
+    return result;
+}
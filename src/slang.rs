@@ -0,0 +1,968 @@
+use crate::{
+    shader_chain::{ShaderChainError, ShaderParameter},
+    texture::Texture,
+};
+use pollster::FutureExt;
+use std::{
+    borrow::Cow,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+use wgpu::util::DeviceExt;
+use winit::dpi::PhysicalSize;
+
+/// Where the `UBO` block goes, whatever binding the shader gives it.
+const UBO_BINDING: u32 = 0;
+/// Push constant blocks become uniform blocks at this binding.
+const PUSH_BINDING: u32 = 1;
+/// Every `sampler2D` becomes a texture at this binding or after, followed by
+/// its sampler.
+const FIRST_TEXTURE_BINDING: u32 = 2;
+/// How deep `#include`s may nest, which stops include cycles.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Quad of positions and texture coordinates, as slang vertex stages take
+/// them at locations 0 and 1.
+const QUAD: [[f32; 6]; 4] = [
+    [0.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+    [1.0, 0.0, 0.0, 1.0, 1.0, 0.0],
+    [0.0, 1.0, 0.0, 1.0, 0.0, 1.0],
+    [1.0, 1.0, 0.0, 1.0, 1.0, 1.0],
+];
+
+/// Column-major matrix mapping the quad to clip space, the top left corner
+/// of the texture to the top left of the target.
+const MVP: [f32; 16] = [
+    2.0, 0.0, 0.0, 0.0, //
+    0.0, -2.0, 0.0, 0.0, //
+    0.0, 0.0, 1.0, 0.0, //
+    -1.0, 1.0, 0.0, 1.0,
+];
+
+/// Texture a slang pass reads, resolved from the name it declares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureInput {
+    /// `Source`, the output of the previous pass or the original image.
+    Source,
+    /// `Original`, and every `OriginalHistoryN`, as a still image never
+    /// changes from one frame to the next.
+    Original,
+    /// `PassOutputN`, or the alias of pass N.
+    PassOutput(usize),
+    /// A lookup texture of the preset, by index.
+    Lookup(usize),
+}
+
+impl TextureInput {
+    /// Resolves `name` for pass `pass_index`, which can only read the passes
+    /// before it.
+    fn resolve(
+        name: &str,
+        pass_index: usize,
+        aliases: &[Option<String>],
+        lookup_names: &[&str],
+    ) -> Option<Self> {
+        let earlier = |index: usize| (index < pass_index).then_some(Self::PassOutput(index));
+        match name {
+            "Source" => Some(Self::Source),
+            "Original" => Some(Self::Original),
+            _ if name
+                .strip_prefix("OriginalHistory")
+                .is_some_and(|n| n.parse::<u32>().is_ok()) =>
+            {
+                Some(Self::Original)
+            }
+            _ => {
+                if let Some(index) = name.strip_prefix("PassOutput").and_then(|n| n.parse().ok()) {
+                    return earlier(index);
+                }
+                if let Some(index) = aliases[..pass_index]
+                    .iter()
+                    .position(|alias| alias.as_deref() == Some(name))
+                {
+                    return earlier(index);
+                }
+                lookup_names
+                    .iter()
+                    .position(|&lookup| lookup == name)
+                    .map(Self::Lookup)
+            }
+        }
+    }
+}
+
+/// What goes into a member of the `UBO` or push constant block, by its name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UniformValue {
+    Mvp,
+    /// `XSize` of a texture `X`, as width, height, 1 / width and 1 / height.
+    TextureSize(TextureInput),
+    OutputSize,
+    FinalViewportSize,
+    FrameCount,
+    FrameDirection,
+    /// A shader parameter of the chain, by index.
+    Parameter(usize),
+    /// Left at zero, which is right for `Rotation`.
+    Unknown,
+}
+
+impl UniformValue {
+    /// Size of the member the value fits, in bytes.
+    fn size(self) -> usize {
+        match self {
+            Self::Mvp => 64,
+            Self::TextureSize(_) | Self::OutputSize | Self::FinalViewportSize => 16,
+            Self::FrameCount | Self::FrameDirection | Self::Parameter(_) | Self::Unknown => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct UniformMember {
+    binding: u32,
+    offset: usize,
+    size: usize,
+    name: String,
+    value: UniformValue,
+}
+
+/// File and line every line of a shader with its includes inlined came from.
+struct Origins {
+    files: Vec<PathBuf>,
+    lines: Vec<(usize, usize)>,
+}
+
+impl Origins {
+    /// `file:line` of a 1-based line of the inlined shader.
+    fn describe(&self, line_number: usize) -> String {
+        match self.lines.get(line_number.wrapping_sub(1)) {
+            Some(&(file, line)) => format!("{}:{line}", self.files[file].display()),
+            None => format!("line {line_number}"),
+        }
+    }
+}
+
+/// A slang shader parsed into one module per stage, not yet bound to any
+/// device.
+pub struct SlangShader {
+    path: PathBuf,
+    vertex: naga::Module,
+    fragment: naga::Module,
+    /// Every `sampler2D` the shader declares, in binding order.
+    textures: Vec<String>,
+    /// Members of the `UBO` and push constant blocks.
+    members: Vec<UniformMember>,
+    /// Size of the blocks at `UBO_BINDING` and `PUSH_BINDING`.
+    block_sizes: [u64; 2],
+    /// Parameters declared with `#pragma parameter`.
+    pub parameters: Vec<ShaderParameter>,
+    /// Name declared with `#pragma name`, which later passes can use like an
+    /// alias.
+    pub name: Option<String>,
+    /// Output format declared with `#pragma format`.
+    pub format: Option<wgpu::TextureFormat>,
+    /// The shader's file and every file it includes.
+    pub files: Vec<PathBuf>,
+}
+
+impl SlangShader {
+    /// Reads the shader at `path` and everything it includes, and parses its
+    /// vertex and fragment stages.
+    pub fn load(path: &Path) -> Result<Self, ShaderChainError> {
+        let compile_error = |message: String| ShaderChainError::Compile(path.to_owned(), message);
+        let mut origins = Origins {
+            files: Vec::new(),
+            lines: Vec::new(),
+        };
+        let mut lines = Vec::new();
+        read_with_includes(path, 0, &mut lines, &mut origins)?;
+
+        // Each stage keeps the lines of the other one blank, so that both
+        // number their lines like the inlined shader.
+        let mut vertex = String::new();
+        let mut fragment = String::new();
+        let mut stage = None;
+        let mut parameters = Vec::new();
+        let mut name = None;
+        let mut format = None;
+        for (number, line) in lines.iter().enumerate() {
+            let origin = || origins.describe(number + 1);
+            let mut keep = line.as_str();
+            if let Some(pragma) = line.trim().strip_prefix("#pragma") {
+                let (directive, rest) = pragma
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((pragma.trim(), ""));
+                let rest = rest.trim();
+                match directive {
+                    "stage" => {
+                        stage = match rest {
+                            "vertex" => Some(naga::ShaderStage::Vertex),
+                            "fragment" => Some(naga::ShaderStage::Fragment),
+                            _ => {
+                                return Err(compile_error(format!(
+                                    "{}: unknown stage \"{rest}\"",
+                                    origin()
+                                )))
+                            }
+                        };
+                        keep = "";
+                    }
+                    "parameter" => {
+                        let parameter = parse_parameter(rest).ok_or_else(|| {
+                            compile_error(format!("{}: invalid parameter", origin()))
+                        })?;
+                        if !parameters
+                            .iter()
+                            .any(|known: &ShaderParameter| known.name == parameter.name)
+                        {
+                            parameters.push(parameter);
+                        }
+                        keep = "";
+                    }
+                    "name" => {
+                        name = Some(rest.to_owned());
+                        keep = "";
+                    }
+                    "format" => {
+                        format = Some(parse_format(rest).ok_or_else(|| {
+                            compile_error(format!("{}: unsupported format \"{rest}\"", origin()))
+                        })?);
+                        keep = "";
+                    }
+                    _ => {}
+                }
+            }
+            let in_vertex = stage != Some(naga::ShaderStage::Fragment);
+            let in_fragment = stage != Some(naga::ShaderStage::Vertex);
+            vertex.push_str(if in_vertex { keep } else { "" });
+            vertex.push('\n');
+            fragment.push_str(if in_fragment { keep } else { "" });
+            fragment.push('\n');
+        }
+
+        let mut textures = Vec::new();
+        let vertex = rewrite_resources(&vertex, &mut textures);
+        let fragment = rewrite_resources(&fragment, &mut textures);
+        let vertex =
+            parse_stage(&vertex, naga::ShaderStage::Vertex, &origins).map_err(compile_error)?;
+        let fragment =
+            parse_stage(&fragment, naga::ShaderStage::Fragment, &origins).map_err(compile_error)?;
+
+        let mut members = Vec::new();
+        let mut block_sizes = [16; 2];
+        for module in [&vertex, &fragment] {
+            reflect_blocks(module, &mut members, &mut block_sizes);
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            vertex,
+            fragment,
+            textures,
+            members,
+            block_sizes,
+            parameters,
+            name,
+            format,
+            files: origins.files,
+        })
+    }
+}
+
+/// A compiled slang pass, drawing a quad with the shader's own vertex stage.
+pub struct SlangPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    vertex_buffer: wgpu::Buffer,
+    /// Buffers at `UBO_BINDING` and `PUSH_BINDING`, and what goes in them.
+    uniform_buffers: [wgpu::Buffer; 2],
+    uniform_data: [Vec<u8>; 2],
+    members: Vec<UniformMember>,
+    textures: Vec<TextureInput>,
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+/// Everything a slang pass can read, once the chain is laid out.
+pub struct Inputs<'a> {
+    pub original: &'a Texture,
+    pub source: &'a Texture,
+    /// Outputs of the passes before this one.
+    pub pass_outputs: &'a [Texture],
+    pub lookup_textures: &'a [(Texture, wgpu::Sampler)],
+    /// Sampler of this pass, for every texture but lookup textures.
+    pub sampler: &'a wgpu::Sampler,
+    pub output_size: PhysicalSize<u32>,
+    pub viewport_size: PhysicalSize<u32>,
+}
+
+impl<'a> Inputs<'a> {
+    fn texture(&self, input: TextureInput) -> (&'a Texture, &'a wgpu::Sampler) {
+        match input {
+            TextureInput::Source => (self.source, self.sampler),
+            TextureInput::Original => (self.original, self.sampler),
+            TextureInput::PassOutput(index) => (&self.pass_outputs[index], self.sampler),
+            TextureInput::Lookup(index) => {
+                let (texture, sampler) = &self.lookup_textures[index];
+                (texture, sampler)
+            }
+        }
+    }
+}
+
+impl SlangPass {
+    /// Compiles `shader` as pass `pass_index` of a chain rendering into
+    /// `format`, resolving the textures and parameters it reads by name.
+    pub fn new(
+        device: &wgpu::Device,
+        shader: SlangShader,
+        format: wgpu::TextureFormat,
+        pass_index: usize,
+        aliases: &[Option<String>],
+        lookup_names: &[&str],
+        parameters: &[ShaderParameter],
+    ) -> Result<Self, ShaderChainError> {
+        let compile_error =
+            |message: String| ShaderChainError::Compile(shader.path.clone(), message);
+        let textures = shader
+            .textures
+            .iter()
+            .map(|name| {
+                TextureInput::resolve(name, pass_index, aliases, lookup_names)
+                    .ok_or_else(|| compile_error(format!("unknown texture \"{name}\"")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut members = shader.members;
+        for member in &mut members {
+            let value = uniform_value(&member.name, pass_index, aliases, lookup_names, parameters);
+            if value == UniformValue::Unknown {
+                log::warn!(
+                    "{}: unknown uniform \"{}\" is left at zero",
+                    shader.path.display(),
+                    member.name
+                );
+            } else if value.size() != member.size {
+                log::warn!(
+                    "{}: uniform \"{}\" has an unexpected type and is left at zero",
+                    shader.path.display(),
+                    member.name
+                );
+            } else {
+                member.value = value;
+            }
+        }
+
+        let mut entries = vec![uniform_entry(UBO_BINDING), uniform_entry(PUSH_BINDING)];
+        for i in 0..textures.len() as u32 {
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: FIRST_TEXTURE_BINDING + 2 * i,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            });
+            entries.push(wgpu::BindGroupLayoutEntry {
+                binding: FIRST_TEXTURE_BINDING + 2 * i + 1,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            });
+        }
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Slang bind group layout"),
+            entries: &entries,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Slang pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let vertex = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Slang vertex shader"),
+            source: wgpu::ShaderSource::Naga(Cow::Owned(shader.vertex)),
+        });
+        let fragment = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Slang fragment shader"),
+            source: wgpu::ShaderSource::Naga(Cow::Owned(shader.fragment)),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Slang pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vertex,
+                entry_point: "main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x2],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &fragment,
+                entry_point: "main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        if let Some(error) = device.pop_error_scope().block_on() {
+            return Err(compile_error(error.to_string()));
+        }
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Slang vertex buffer"),
+            contents: bytemuck::cast_slice(&QUAD),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let uniform_buffers = shader.block_sizes.map(|size| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Slang uniform buffer"),
+                size,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        });
+        let uniform_data = shader.block_sizes.map(|size| vec![0; size as usize]);
+
+        Ok(Self {
+            pipeline,
+            bind_group_layout,
+            vertex_buffer,
+            uniform_buffers,
+            uniform_data,
+            members,
+            textures,
+            bind_group: None,
+        })
+    }
+
+    /// Binds the textures of `inputs` and fills in every uniform that stays
+    /// the same from frame to frame.
+    pub fn set_layout(&mut self, device: &wgpu::Device, inputs: &Inputs) {
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: UBO_BINDING,
+                resource: self.uniform_buffers[0].as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: PUSH_BINDING,
+                resource: self.uniform_buffers[1].as_entire_binding(),
+            },
+        ];
+        for (i, &input) in self.textures.iter().enumerate() {
+            let (texture, sampler) = inputs.texture(input);
+            let binding = FIRST_TEXTURE_BINDING + 2 * i as u32;
+            entries.push(wgpu::BindGroupEntry {
+                binding,
+                resource: wgpu::BindingResource::TextureView(&texture.view),
+            });
+            entries.push(wgpu::BindGroupEntry {
+                binding: binding + 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            });
+        }
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Slang bind group"),
+            layout: &self.bind_group_layout,
+            entries: &entries,
+        }));
+
+        for member in &self.members {
+            let size = match member.value {
+                UniformValue::Mvp => {
+                    write_member(&mut self.uniform_data, member, bytemuck::cast_slice(&MVP));
+                    continue;
+                }
+                UniformValue::TextureSize(input) => inputs.texture(input).0.size(),
+                UniformValue::OutputSize => inputs.output_size,
+                UniformValue::FinalViewportSize => inputs.viewport_size,
+                UniformValue::FrameDirection => {
+                    write_member(&mut self.uniform_data, member, &1i32.to_ne_bytes());
+                    continue;
+                }
+                _ => continue,
+            };
+            let (width, height) = (size.width as f32, size.height as f32);
+            let size = [width, height, 1.0 / width, 1.0 / height];
+            write_member(&mut self.uniform_data, member, bytemuck::cast_slice(&size));
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        frame_count: u32,
+        parameters: &[ShaderParameter],
+    ) {
+        for member in &self.members {
+            match member.value {
+                UniformValue::FrameCount => {
+                    write_member(&mut self.uniform_data, member, &frame_count.to_ne_bytes())
+                }
+                UniformValue::Parameter(index) => write_member(
+                    &mut self.uniform_data,
+                    member,
+                    &parameters[index].value.to_ne_bytes(),
+                ),
+                _ => {}
+            }
+        }
+        for (buffer, data) in self.uniform_buffers.iter().zip(&self.uniform_data) {
+            queue.write_buffer(buffer, 0, data);
+        }
+
+        let mut render_pass =
+            crate::renderer::begin_pass(encoder, view, wgpu::Color::TRANSPARENT, "Slang pass");
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(
+            0,
+            self.bind_group
+                .as_ref()
+                .expect("slang passes are laid out before they render"),
+            &[],
+        );
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..4, 0..1);
+    }
+}
+
+fn uniform_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+/// Copies `bytes` into `member`, which has their size.
+fn write_member(data: &mut [Vec<u8>; 2], member: &UniformMember, bytes: &[u8]) {
+    let block = &mut data[(member.binding - UBO_BINDING) as usize];
+    block[member.offset..member.offset + member.size].copy_from_slice(bytes);
+}
+
+fn uniform_value(
+    name: &str,
+    pass_index: usize,
+    aliases: &[Option<String>],
+    lookup_names: &[&str],
+    parameters: &[ShaderParameter],
+) -> UniformValue {
+    match name {
+        "MVP" => UniformValue::Mvp,
+        "OutputSize" => UniformValue::OutputSize,
+        "FinalViewportSize" => UniformValue::FinalViewportSize,
+        "FrameCount" => UniformValue::FrameCount,
+        "FrameDirection" => UniformValue::FrameDirection,
+        _ => {
+            if let Some(index) = parameters.iter().position(|p| p.name == name) {
+                return UniformValue::Parameter(index);
+            }
+            name.strip_suffix("Size")
+                .and_then(|texture| {
+                    TextureInput::resolve(texture, pass_index, aliases, lookup_names)
+                })
+                .map_or(UniformValue::Unknown, UniformValue::TextureSize)
+        }
+    }
+}
+
+fn read_with_includes(
+    path: &Path,
+    depth: usize,
+    lines: &mut Vec<String>,
+    origins: &mut Origins,
+) -> Result<(), ShaderChainError> {
+    let text = fs::read_to_string(path).map_err(|e| ShaderChainError::Io(path.to_owned(), e))?;
+    let file = origins.files.len();
+    origins.files.push(path.to_owned());
+    for (number, line) in text.lines().enumerate() {
+        match line.trim().strip_prefix("#include") {
+            Some(include) if depth < MAX_INCLUDE_DEPTH => {
+                let include = include.trim().trim_matches('"');
+                let directory = path.parent().unwrap_or(Path::new(""));
+                read_with_includes(&directory.join(include), depth + 1, lines, origins)?;
+            }
+            Some(_) => {
+                return Err(ShaderChainError::Compile(
+                    path.to_owned(),
+                    format!("line {}: includes nest too deep", number + 1),
+                ))
+            }
+            None => {
+                lines.push(line.to_owned());
+                origins.lines.push((file, number + 1));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses `NAME "Description" default minimum maximum [step]`, the rest of a
+/// `#pragma parameter` line in slang and WGSL passes alike.
+pub fn parse_parameter(s: &str) -> Option<ShaderParameter> {
+    let (name, rest) = s.split_once(char::is_whitespace)?;
+    let (description, numbers) = rest.trim_start().strip_prefix('"')?.split_once('"')?;
+    let numbers = numbers
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<f32>, _>>()
+        .ok()?;
+    let (default, min, max, step) = match numbers[..] {
+        [default, min, max] => (default, min, max, (max - min) / 10.0),
+        [default, min, max, step] => (default, min, max, step),
+        _ => return None,
+    };
+    Some(ShaderParameter {
+        name: name.to_owned(),
+        description: description.to_owned(),
+        default,
+        min,
+        max,
+        step,
+        value: default,
+    })
+}
+
+/// Maps a Vulkan format name to the closest format that can be both rendered
+/// to and filtered, which stores 32-bit floats at 16 bits.
+fn parse_format(s: &str) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat::*;
+    Some(match s {
+        "R8_UNORM" => R8Unorm,
+        "R8G8_UNORM" => Rg8Unorm,
+        "R8G8B8A8_UNORM" => Rgba8Unorm,
+        "R8G8B8A8_SRGB" => Rgba8UnormSrgb,
+        "A2B10G10R10_UNORM_PACK32" => Rgb10a2Unorm,
+        "R16_SFLOAT" | "R32_SFLOAT" => R16Float,
+        "R16G16_SFLOAT" | "R32G32_SFLOAT" => Rg16Float,
+        "R16G16B16A16_SFLOAT" | "R32G32B32A32_SFLOAT" => Rgba16Float,
+        _ => return None,
+    })
+}
+
+/// Moves the shader's blocks to fixed bindings, turning push constants into
+/// a uniform block, and splits every `sampler2D` into a texture and a
+/// sampler, which naga's GLSL frontend can't combine in a declaration.
+/// Names of the textures are added to `textures`, in binding order.
+fn rewrite_resources(source: &str, textures: &mut Vec<String>) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = find_word(rest, "layout") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + "layout".len()..];
+        let declaration = after.trim_start().strip_prefix('(').and_then(|qualifiers| {
+            let (qualifiers, tail) = qualifiers.split_once(')')?;
+            let tail = strip_word(tail.trim_start(), "uniform")?.trim_start();
+            Some((qualifiers, tail))
+        });
+        let Some((qualifiers, tail)) = declaration else {
+            out.push_str("layout");
+            rest = after;
+            continue;
+        };
+        match strip_word(tail, "sampler2D").and_then(|name| name.split_once(';')) {
+            Some((name, tail)) => {
+                let name = name.trim();
+                let index = match textures.iter().position(|known| known == name) {
+                    Some(index) => index,
+                    None => {
+                        textures.push(name.to_owned());
+                        textures.len() - 1
+                    }
+                } as u32;
+                let binding = FIRST_TEXTURE_BINDING + 2 * index;
+                out.push_str(&format!(
+                    "layout(set = 0, binding = {binding}) uniform texture2D {name}_texture; \
+                     layout(set = 0, binding = {}) uniform sampler {name}_sampler;",
+                    binding + 1
+                ));
+                rest = tail;
+            }
+            None => {
+                let binding = if qualifiers.contains("push_constant") {
+                    PUSH_BINDING
+                } else {
+                    UBO_BINDING
+                };
+                out.push_str(&format!(
+                    "layout(set = 0, binding = {binding}, std140) uniform "
+                ));
+                rest = tail;
+            }
+        }
+    }
+    out.push_str(rest);
+
+    for name in textures.iter() {
+        out = replace_word(
+            &out,
+            name,
+            &format!("sampler2D({name}_texture, {name}_sampler)"),
+        );
+    }
+    out
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Byte offset of the first `word` in `s` that isn't part of a longer
+/// identifier.
+fn find_word(s: &str, word: &str) -> Option<usize> {
+    s.match_indices(word).map(|(i, _)| i).find(|&i| {
+        !s[..i].ends_with(is_identifier) && !s[i + word.len()..].starts_with(is_identifier)
+    })
+}
+
+fn strip_word<'a>(s: &'a str, word: &str) -> Option<&'a str> {
+    s.strip_prefix(word)
+        .filter(|rest| !rest.starts_with(is_identifier))
+}
+
+fn replace_word(s: &str, word: &str, replacement: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = find_word(rest, word) {
+        out.push_str(&rest[..i]);
+        out.push_str(replacement);
+        rest = &rest[i + word.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn parse_stage(
+    source: &str,
+    stage: naga::ShaderStage,
+    origins: &Origins,
+) -> Result<naga::Module, String> {
+    let module = naga::front::glsl::Frontend::default()
+        .parse(&naga::front::glsl::Options::from(stage), source)
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(|error| {
+                    let line = error.meta.location(source).line_number as usize;
+                    format!("{}: {}", origins.describe(line), error.kind)
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .map_err(|error| {
+        let mut message = match error.location(source) {
+            Some(location) => format!("{}: ", origins.describe(location.line_number as usize)),
+            None => String::new(),
+        };
+        let mut cause: Option<&dyn Error> = Some(error.as_inner());
+        while let Some(error) = cause {
+            message.push_str(&error.to_string());
+            cause = error.source();
+            if cause.is_some() {
+                message.push_str(": ");
+            }
+        }
+        message
+    })?;
+    Ok(module)
+}
+
+/// Adds the members of the uniform blocks at `UBO_BINDING` and
+/// `PUSH_BINDING` in `module` that aren't in `members` yet.
+fn reflect_blocks(module: &naga::Module, members: &mut Vec<UniformMember>, sizes: &mut [u64; 2]) {
+    for (_, variable) in module.global_variables.iter() {
+        let binding = match (&variable.space, &variable.binding) {
+            (naga::AddressSpace::Uniform, Some(binding))
+                if binding.group == 0 && binding.binding <= PUSH_BINDING =>
+            {
+                binding.binding
+            }
+            _ => continue,
+        };
+        let naga::TypeInner::Struct {
+            members: block,
+            span,
+        } = &module.types[variable.ty].inner
+        else {
+            continue;
+        };
+        let size = &mut sizes[(binding - UBO_BINDING) as usize];
+        *size = (*size).max(u64::from(*span).next_multiple_of(16));
+        for member in block {
+            let Some(name) = &member.name else { continue };
+            if members
+                .iter()
+                .any(|known| known.binding == binding && &known.name == name)
+            {
+                continue;
+            }
+            // Semantics and parameters are 32-bit scalars, vec4s or mat4s.
+            let size = match module.types[member.ty].inner {
+                naga::TypeInner::Matrix {
+                    columns: naga::VectorSize::Quad,
+                    rows: naga::VectorSize::Quad,
+                    width: 4,
+                } => 64,
+                naga::TypeInner::Vector {
+                    size: naga::VectorSize::Quad,
+                    width: 4,
+                    ..
+                } => 16,
+                naga::TypeInner::Scalar { width: 4, kind } if kind != naga::ScalarKind::Bool => 4,
+                _ => continue,
+            };
+            members.push(UniformMember {
+                binding,
+                offset: member.offset as usize,
+                size,
+                name: name.clone(),
+                value: UniformValue::Unknown,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_parameters() {
+        assert_eq!(
+            parse_parameter("SCANLINES \"Scanline strength\" 0.5 0.0 1.0 0.05"),
+            Some(ShaderParameter {
+                name: "SCANLINES".to_owned(),
+                description: "Scanline strength".to_owned(),
+                default: 0.5,
+                min: 0.0,
+                max: 1.0,
+                step: 0.05,
+                value: 0.5,
+            })
+        );
+        // Without a step, it takes ten steps to go from the minimum to the
+        // maximum.
+        let parameter = parse_parameter("MASK\t\"Mask\"  2 0 4").unwrap();
+        assert_eq!(parameter.description, "Mask");
+        assert_eq!((parameter.default, parameter.step), (2.0, 0.4));
+    }
+
+    #[test]
+    fn rejects_malformed_parameters() {
+        for line in [
+            "",
+            "NAME",
+            "NAME Description 0 0 1",
+            "NAME \"Description 0 0 1",
+            "NAME \"Description\" 0 1",
+            "NAME \"Description\" 0 0 1 0.1 2",
+            "NAME \"Description\" zero 0 1",
+        ] {
+            assert_eq!(parse_parameter(line), None, "{line}");
+        }
+    }
+
+    #[test]
+    fn rewrites_blocks_and_samplers_to_fixed_bindings() {
+        let source = "\
+layout(push_constant) uniform Push { vec4 SourceSize; } params;
+layout(std140, set = 0, binding = 0) uniform UBO { mat4 MVP; } global;
+layout(location = 0) in vec2 vTexCoord;
+layout(set = 0, binding = 2) uniform sampler2D Source;
+layout(set = 0, binding = 3) uniform sampler2D Mask;
+void main() { FragColor = texture(Source, vTexCoord) * texture(Mask, vTexCoord); }
+";
+        let mut textures = Vec::new();
+        assert_eq!(
+            rewrite_resources(source, &mut textures),
+            "\
+layout(set = 0, binding = 1, std140) uniform Push { vec4 SourceSize; } params;
+layout(set = 0, binding = 0, std140) uniform UBO { mat4 MVP; } global;
+layout(location = 0) in vec2 vTexCoord;
+layout(set = 0, binding = 2) uniform texture2D Source_texture; \
+layout(set = 0, binding = 3) uniform sampler Source_sampler;
+layout(set = 0, binding = 4) uniform texture2D Mask_texture; \
+layout(set = 0, binding = 5) uniform sampler Mask_sampler;
+void main() { FragColor = texture(sampler2D(Source_texture, Source_sampler), vTexCoord) \
+* texture(sampler2D(Mask_texture, Mask_sampler), vTexCoord); }
+"
+        );
+        assert_eq!(textures, ["Source", "Mask"]);
+    }
+
+    #[test]
+    fn keeps_the_bindings_of_textures_another_stage_declared() {
+        let mut textures = vec!["Mask".to_owned()];
+        let rewritten = rewrite_resources(
+            "layout(binding = 1) uniform sampler2D Source;\n\
+             layout(binding = 2) uniform sampler2D Mask;\n",
+            &mut textures,
+        );
+        assert!(rewritten.contains("binding = 4) uniform texture2D Source_texture"));
+        assert!(rewritten.contains("binding = 2) uniform texture2D Mask_texture"));
+        assert_eq!(textures, ["Mask", "Source"]);
+    }
+
+    #[test]
+    fn resolves_texture_names() {
+        let aliases = [
+            None,
+            Some("First".to_owned()),
+            None,
+            Some("Later".to_owned()),
+        ];
+        let lookup_names = ["Mask"];
+        let resolve = |name| TextureInput::resolve(name, 2, &aliases, &lookup_names);
+        assert_eq!(resolve("Source"), Some(TextureInput::Source));
+        assert_eq!(resolve("Original"), Some(TextureInput::Original));
+        assert_eq!(resolve("OriginalHistory3"), Some(TextureInput::Original));
+        assert_eq!(resolve("PassOutput0"), Some(TextureInput::PassOutput(0)));
+        assert_eq!(resolve("First"), Some(TextureInput::PassOutput(1)));
+        assert_eq!(resolve("Mask"), Some(TextureInput::Lookup(0)));
+    }
+
+    #[test]
+    fn only_resolves_passes_before_the_reading_one() {
+        let aliases = [
+            None,
+            Some("First".to_owned()),
+            None,
+            Some("Later".to_owned()),
+        ];
+        let resolve = |name, pass_index| TextureInput::resolve(name, pass_index, &aliases, &[]);
+        assert_eq!(resolve("PassOutput2", 2), None);
+        assert_eq!(resolve("PassOutput3", 2), None);
+        assert_eq!(resolve("First", 1), None);
+        assert_eq!(resolve("Later", 2), None);
+        assert_eq!(resolve("OriginalHistory", 2), None);
+        assert_eq!(resolve("Unknown", 2), None);
+    }
+}
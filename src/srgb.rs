@@ -0,0 +1,192 @@
+use crate::{
+    blit::{self, Blit, BlitPipeline, ExtraBindings},
+    color::LinearColor,
+    renderer,
+    scaling::Viewport,
+    texture::Texture,
+};
+use winit::dpi::PhysicalSize;
+
+/// Format of the texture an [`SrgbEncoder`] has frames rendered into.
+pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Format of the frames that [`encode_image`] reads back.
+pub const IMAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Whether a target of `format` takes sRGB-encoded colors without the GPU
+/// encoding them, as UNORM surfaces do. Float formats take linear colors.
+pub fn needs_encoding(format: wgpu::TextureFormat) -> bool {
+    !format.is_srgb()
+        && !matches!(
+            format,
+            wgpu::TextureFormat::Rgba16Float | wgpu::TextureFormat::Rgba32Float
+        )
+}
+
+/// Stand-in for surfaces that need encoding and can't be viewed as sRGB
+/// either. Frames are rendered into an sRGB texture, which
+/// [`SrgbEncoder::render`] encodes into the surface, so that blending and
+/// filtering still happen on linear colors.
+pub struct SrgbEncoder {
+    pipeline: wgpu::RenderPipeline,
+    sampler: wgpu::Sampler,
+    texture: Texture,
+    blit: Blit,
+}
+
+impl SrgbEncoder {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        blit_pipeline: &BlitPipeline,
+        surface_format: wgpu::TextureFormat,
+        size: PhysicalSize<u32>,
+    ) -> Self {
+        let pipeline = blit_pipeline.create_pipeline(
+            device,
+            surface_format,
+            "fs_encode_srgb",
+            ExtraBindings::None,
+        );
+        let sampler = blit::create_sampler(device, wgpu::FilterMode::Nearest);
+        let (texture, blit) = create_target(device, queue, blit_pipeline, &sampler, size);
+        Self {
+            pipeline,
+            sampler,
+            texture,
+            blit,
+        }
+    }
+
+    pub fn resize(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        blit_pipeline: &BlitPipeline,
+        size: PhysicalSize<u32>,
+    ) {
+        (self.texture, self.blit) =
+            create_target(device, queue, blit_pipeline, &self.sampler, size);
+    }
+
+    /// The view to render frames into instead of the surface.
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.texture.view
+    }
+
+    /// Encodes the frame into `surface_view`.
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, surface_view: &wgpu::TextureView) {
+        let mut render_pass = renderer::begin_pass(
+            encoder,
+            surface_view,
+            wgpu::Color::TRANSPARENT,
+            "sRGB encoding pass",
+        );
+        self.blit.draw(&mut render_pass, &self.pipeline);
+    }
+}
+
+fn create_target(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    blit_pipeline: &BlitPipeline,
+    sampler: &wgpu::Sampler,
+    size: PhysicalSize<u32>,
+) -> (Texture, Blit) {
+    let texture = Texture::render_target(device, size, FORMAT, Some("sRGB encoder texture"));
+    let blit = Blit::new(device, blit_pipeline, &texture.view, sampler);
+    blit.set_viewport(queue, Viewport::covering(size), size);
+    (texture, blit)
+}
+
+/// Turns the premultiplied linear colors of a frame read back from an
+/// [`IMAGE_FORMAT`] texture into the straight sRGB ones that image files store.
+pub fn encode_image(size: PhysicalSize<u32>, pixels: &[u8]) -> image::RgbaImage {
+    let mut image = image::RgbaImage::new(size.width, size.height);
+    for (pixel, channels) in image.pixels_mut().zip(pixels.chunks_exact(8)) {
+        let channel = |i: usize| f16_to_f64(u16::from_ne_bytes([channels[i], channels[i + 1]]));
+        let color = LinearColor {
+            r: channel(0),
+            g: channel(2),
+            b: channel(4),
+            a: channel(6),
+        }
+        .unpremultiplied()
+        .to_srgb();
+        *pixel = image::Rgba([color.r, color.g, color.b, color.a]);
+    }
+    image
+}
+
+/// Decodes a half-precision float.
+fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f64::from(bits & 0x3ff);
+    sign * match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        0x1f if mantissa == 0.0 => f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f64.powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::U8Color;
+
+    /// Encodes a color channel from 0 to 1 as a half-precision float, rounding
+    /// to the nearest one like the GPU does.
+    fn f16_bits(value: f64) -> u16 {
+        if value < 2f64.powi(-14) {
+            return (value * 2f64.powi(24)).round() as u16;
+        }
+        let exponent = value.log2().floor() as i32;
+        let mantissa = ((value / 2f64.powi(exponent) - 1.0) * 1024.0).round() as u16;
+        // A mantissa rounded up to 1024 carries into the exponent.
+        (((exponent + 15) as u16) << 10) + mantissa
+    }
+
+    #[test]
+    fn decodes_half_floats() {
+        assert_eq!(f16_to_f64(0x0000), 0.0);
+        assert!(f16_to_f64(0x8000).is_sign_negative());
+        assert_eq!(f16_to_f64(0x0001), 2f64.powi(-24));
+        assert_eq!(f16_to_f64(0x03ff), 1023.0 * 2f64.powi(-24));
+        assert_eq!(f16_to_f64(0x0400), 2f64.powi(-14));
+        assert_eq!(f16_to_f64(0x3c00), 1.0);
+        assert_eq!(f16_to_f64(0xbc00), -1.0);
+        assert_eq!(f16_to_f64(0x3555), 0.333251953125);
+        assert_eq!(f16_to_f64(0x7bff), 65504.0);
+        assert_eq!(f16_to_f64(0x7c00), f64::INFINITY);
+        assert_eq!(f16_to_f64(0xfc00), f64::NEG_INFINITY);
+        assert!(f16_to_f64(0x7e00).is_nan());
+        assert!(f16_to_f64(0x7c01).is_nan());
+    }
+
+    #[test]
+    fn encodes_every_channel_value_back_unchanged() {
+        let colors: Vec<_> = (0..=255)
+            .map(|value| U8Color::from_rgba(value, 255 - value, value, 255))
+            .chain((1..=255).map(|alpha| U8Color::from_rgba(255, 255, 255, alpha)))
+            .collect();
+        let pixels: Vec<u8> = colors
+            .iter()
+            .flat_map(|color| {
+                let linear = color.to_linear().premultiplied();
+                [linear.r, linear.g, linear.b, linear.a]
+            })
+            .flat_map(|channel| f16_bits(channel).to_ne_bytes())
+            .collect();
+
+        let image = encode_image(PhysicalSize::new(colors.len() as u32, 1), &pixels);
+        for (pixel, color) in image.pixels().zip(&colors) {
+            assert_eq!(pixel.0, [color.r, color.g, color.b, color.a], "{color:?}");
+        }
+
+        // Nothing is left of the color of fully transparent pixels.
+        let image = encode_image(PhysicalSize::new(1, 1), &[0; 8]);
+        assert_eq!(image.get_pixel(0, 0).0, [0; 4]);
+    }
+}
@@ -0,0 +1,187 @@
+use std::path::Path;
+use winit::dpi::PhysicalSize;
+
+pub fn load_image(path: &Path) -> image::ImageResult<image::RgbaImage> {
+    Ok(image::open(path)?.into_rgba8())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Texture size {0}x{1} exceeds the device limit of {2}")]
+pub struct TextureTooLarge(pub u32, pub u32, pub u32);
+
+/// Fails if `device` can't create textures of `size`.
+pub fn check_size(device: &wgpu::Device, size: PhysicalSize<u32>) -> Result<(), TextureTooLarge> {
+    let max_size = device.limits().max_texture_dimension_2d;
+    if size.width > max_size || size.height > max_size {
+        return Err(TextureTooLarge(size.width, size.height, max_size));
+    }
+    Ok(())
+}
+
+pub struct Texture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Texture {
+    pub fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.width, self.height)
+    }
+
+    pub fn from_image(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &image::RgbaImage,
+        label: Option<&str>,
+    ) -> Self {
+        Self::from_image_with_format(
+            device,
+            queue,
+            image,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            label,
+        )
+    }
+
+    /// Like [`Texture::from_image`], for images that hold something other than
+    /// sRGB colors in an RGBA8 format.
+    pub fn from_image_with_format(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &image::RgbaImage,
+        format: wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Self {
+        let (width, height) = image.dimensions();
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            image,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            texture,
+            view,
+            width,
+            height,
+        }
+    }
+
+    pub fn render_target(
+        device: &wgpu::Device,
+        size: PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            texture,
+            view,
+            width: size.width,
+            height: size.height,
+        }
+    }
+}
+
+/// Copies a texture back into memory, row after row without padding,
+/// blocking until the GPU is done.
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &Texture,
+) -> Result<Vec<u8>, wgpu::BufferAsyncError> {
+    let bytes_per_pixel = texture
+        .texture
+        .format()
+        .block_size(None)
+        .expect("render targets have color formats");
+    let unpadded_bytes_per_row = bytes_per_pixel * texture.width;
+    let bytes_per_row = unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback buffer"),
+        size: (bytes_per_row * texture.height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Readback encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(texture.height),
+            },
+        },
+        texture.texture.size(),
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .expect("map_async callback is called by the poll above")?;
+
+    let data = slice.get_mapped_range();
+    let pixels = data
+        .chunks_exact(bytes_per_row as usize)
+        .flat_map(|row| &row[..unpadded_bytes_per_row as usize])
+        .copied()
+        .collect();
+    drop(data);
+    buffer.unmap();
+    Ok(pixels)
+}
@@ -0,0 +1,318 @@
+use crate::scaling::{Layout, ScaleMode, Scaling};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+
+/// Zoom levels between whole ones with fractional zoom.
+const FRACTIONAL_STEP: f64 = 0.25;
+/// Pixels of the image that panning keeps inside the window.
+const MIN_VISIBLE: u32 = 16;
+/// Touchpad scrolling in pixels that makes one zoom step.
+const PIXELS_PER_STEP: f64 = 50.0;
+
+/// Zoom and pan over the layout of a [`Scaling`], driven by the mouse: the
+/// wheel steps through zoom levels around the cursor and dragging pans.
+///
+/// Zooming sets the scaling's fixed scale. Panning moves the image by whole
+/// screen pixels, so that at whole zoom levels every source pixel keeps
+/// covering the same number of screen pixels.
+#[derive(Debug, Clone, Default)]
+pub struct View {
+    /// Step through quarter zoom levels in modes that can draw them.
+    fractional_zoom: bool,
+    /// Offset of the image from where the scaling places it.
+    pan: PhysicalPosition<f64>,
+    cursor: PhysicalPosition<f64>,
+    /// Whether the left button is down to drag the image around.
+    dragging: bool,
+    /// Touchpad scrolling not yet making a whole zoom step.
+    scrolled: f64,
+}
+
+impl View {
+    pub fn new(fractional_zoom: bool) -> Self {
+        Self {
+            fractional_zoom,
+            ..Self::default()
+        }
+    }
+
+    /// The scaling's layout, moved by the pan. Limits the pan to what keeps
+    /// some of the image inside the target.
+    pub fn layout(
+        &mut self,
+        scaling: &Scaling,
+        source: PhysicalSize<u32>,
+        target: PhysicalSize<u32>,
+    ) -> Layout {
+        let mut layout = scaling.layout(source, target);
+        let viewport = &mut layout.viewport;
+        viewport.x = pan_axis(viewport.x, viewport.width, target.width, &mut self.pan.x);
+        viewport.y = pan_axis(viewport.y, viewport.height, target.height, &mut self.pan.y);
+        layout
+    }
+
+    /// Returns whether the view changed.
+    pub fn cursor_moved(&mut self, position: PhysicalPosition<f64>) -> bool {
+        let previous = std::mem::replace(&mut self.cursor, position);
+        if !self.dragging {
+            return false;
+        }
+        self.pan.x += position.x - previous.x;
+        self.pan.y += position.y - previous.y;
+        true
+    }
+
+    pub fn cursor(&self) -> PhysicalPosition<f64> {
+        self.cursor
+    }
+
+    pub fn set_dragging(&mut self, dragging: bool) {
+        self.dragging = dragging;
+    }
+
+    /// Puts the image back where the scaling places it.
+    pub fn reset(&mut self) {
+        self.pan = PhysicalPosition::default();
+        self.scrolled = 0.0;
+    }
+
+    /// Zooms `steps` levels in, or out if negative, keeping the point of the
+    /// image at `anchor` on the target in place. Never zooms beyond what
+    /// passes of `max_size` hold, and not at all if even the smallest level
+    /// needs larger ones.
+    pub fn zoom(
+        &mut self,
+        scaling: &mut Scaling,
+        steps: i32,
+        source: PhysicalSize<u32>,
+        target: PhysicalSize<u32>,
+        max_size: u32,
+        anchor: PhysicalPosition<f64>,
+    ) {
+        let Some(max_zoom) = self.max_zoom(scaling, source, target, max_size) else {
+            return;
+        };
+        let before = self.layout(scaling, source, target).viewport;
+        let zoom = before.height as f64 / source.height as f64;
+        let unit = self.unit(scaling.mode);
+        // Off-level zooms, like those that fit the window, go to the next
+        // level in the direction of the step.
+        let level = zoom / unit;
+        let level = if steps > 0 {
+            (level + 1e-6).floor() + steps as f64
+        } else {
+            (level - 1e-6).ceil() + steps as f64
+        };
+        scaling.scale = Some((level * unit).clamp(unit, max_zoom));
+
+        // Where the scaling places the zoomed image, and the pan that brings
+        // the point that was at the anchor back to it.
+        let after = scaling.layout(source, target).viewport;
+        let pan = |anchor: f64, position: i32, size: u32, new_position: i32, new_size: u32| {
+            let fraction = (anchor - position as f64) / size as f64;
+            anchor - fraction * new_size as f64 - new_position as f64
+        };
+        self.pan = PhysicalPosition::new(
+            pan(anchor.x, before.x, before.width, after.x, after.width).round(),
+            pan(anchor.y, before.y, before.height, after.y, after.height).round(),
+        );
+    }
+
+    /// Largest zoom level whose passes are at most `max_size` on a side, or
+    /// `None` if even the smallest one needs larger passes.
+    pub fn max_zoom(
+        &self,
+        scaling: &Scaling,
+        source: PhysicalSize<u32>,
+        target: PhysicalSize<u32>,
+        max_size: u32,
+    ) -> Option<f64> {
+        scaling.max_scale(source, target, self.unit(scaling.mode), max_size)
+    }
+
+    /// Distance between zoom levels in `mode`.
+    fn unit(&self, mode: ScaleMode) -> f64 {
+        let fractional =
+            self.fractional_zoom && matches!(mode, ScaleMode::SharpBilinear | ScaleMode::Coverage);
+        if fractional {
+            FRACTIONAL_STEP
+        } else {
+            1.0
+        }
+    }
+
+    /// Zoom steps from touchpad scrolling by `pixels`, once they add up to
+    /// whole ones.
+    pub fn scroll_steps(&mut self, pixels: f64) -> i32 {
+        if !pixels.is_finite() {
+            return 0;
+        }
+        self.scrolled += pixels;
+        let steps = (self.scrolled / PIXELS_PER_STEP).trunc();
+        self.scrolled -= steps * PIXELS_PER_STEP;
+        steps as i32
+    }
+}
+
+/// Position of an image moved by `pan` along one axis, in whole pixels.
+/// Limits `pan` to what keeps some of the image inside the target.
+fn pan_axis(position: i32, size: u32, target: u32, pan: &mut f64) -> i32 {
+    let visible = MIN_VISIBLE.min(size).min(target) as i32;
+    let min = visible - size as i32 - position;
+    let max = target as i32 - visible - position;
+    *pan = pan.clamp(min as f64, max as f64);
+    position + pan.round() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: PhysicalSize<u32> = PhysicalSize::new(8, 6);
+    const TARGET: PhysicalSize<u32> = PhysicalSize::new(64, 48);
+    const CENTER: PhysicalPosition<f64> = PhysicalPosition::new(32.0, 24.0);
+
+    fn zoomed(view: &mut View, scaling: &mut Scaling, steps: i32, max_size: u32) -> Option<f64> {
+        view.zoom(scaling, steps, SOURCE, TARGET, max_size, CENTER);
+        scaling.scale
+    }
+
+    #[test]
+    fn zooms_by_whole_levels() {
+        let mut view = View::new(true);
+        let mut scaling = Scaling::default();
+        assert_eq!(zoomed(&mut view, &mut scaling, 1, 800), Some(9.0));
+        assert_eq!(zoomed(&mut view, &mut scaling, -2, 800), Some(7.0));
+    }
+
+    #[test]
+    fn zooms_by_quarter_levels_in_fractional_modes() {
+        let mut view = View::new(true);
+        let mut scaling = Scaling {
+            mode: ScaleMode::SharpBilinear,
+            ..Scaling::default()
+        };
+        let target = PhysicalSize::new(80, 50);
+        // Fitting the target zooms by 50 / 6, between levels.
+        view.zoom(&mut scaling, 1, SOURCE, target, 800, CENTER);
+        assert_eq!(scaling.scale, Some(8.5));
+        scaling.scale = None;
+        view.zoom(&mut scaling, -1, SOURCE, target, 800, CENTER);
+        assert_eq!(scaling.scale, Some(8.25));
+
+        let mut view = View::new(false);
+        scaling.scale = None;
+        view.zoom(&mut scaling, 1, SOURCE, target, 800, CENTER);
+        assert_eq!(scaling.scale, Some(9.0));
+        scaling.scale = None;
+        view.zoom(&mut scaling, -1, SOURCE, target, 800, CENTER);
+        assert_eq!(scaling.scale, Some(8.0));
+    }
+
+    #[test]
+    fn clamps_the_zoom() {
+        let mut view = View::default();
+        let mut scaling = Scaling::default();
+        assert_eq!(zoomed(&mut view, &mut scaling, 20, 80), Some(10.0));
+        assert_eq!(zoomed(&mut view, &mut scaling, -20, 80), Some(1.0));
+        // Without passes, even a limit below the size of the image leaves the
+        // smallest level.
+        assert_eq!(zoomed(&mut view, &mut scaling, 1, 4), Some(1.0));
+
+        let mut view = View::new(true);
+        let mut scaling = Scaling {
+            mode: ScaleMode::Coverage,
+            ..Scaling::default()
+        };
+        assert_eq!(zoomed(&mut view, &mut scaling, -100, 80), Some(0.25));
+    }
+
+    #[test]
+    fn limits_the_zoom_to_passes_that_fit() {
+        let mut view = View::default();
+        let mut scaling = Scaling {
+            mode: ScaleMode::Scale3x,
+            chain: true,
+            ..Scaling::default()
+        };
+        // Zooming by 12 would chain three passes, 27 times the image.
+        assert_eq!(zoomed(&mut view, &mut scaling, 20, 100), Some(9.0));
+
+        // Not even a single pass fits, so zooming does nothing.
+        let mut scaling = Scaling {
+            mode: ScaleMode::Scale3x,
+            ..Scaling::default()
+        };
+        assert_eq!(zoomed(&mut view, &mut scaling, 1, 16), None);
+    }
+
+    #[test]
+    fn keeps_the_anchor_in_place_when_zooming() {
+        let mut view = View::default();
+        let mut scaling = Scaling {
+            scale: Some(2.0),
+            ..Scaling::default()
+        };
+        let before = view.layout(&scaling, SOURCE, TARGET).viewport;
+        let anchor = PhysicalPosition::new(before.x as f64, before.y as f64);
+        view.zoom(&mut scaling, 1, SOURCE, TARGET, 800, anchor);
+        let after = view.layout(&scaling, SOURCE, TARGET).viewport;
+        assert_eq!((after.x, after.y), (before.x, before.y));
+        assert_eq!(after.size(), PhysicalSize::new(24, 18));
+    }
+
+    #[test]
+    fn keeps_some_of_the_image_inside_the_target() {
+        let mut pan = 100.0;
+        assert_eq!(pan_axis(24, 16, 64, &mut pan), 48);
+        assert_eq!(pan, 24.0);
+        let mut pan = -100.0;
+        assert_eq!(pan_axis(24, 16, 64, &mut pan), 0);
+        assert_eq!(pan, -24.0);
+        let mut pan = 10.4;
+        assert_eq!(pan_axis(24, 16, 64, &mut pan), 34);
+
+        // Images larger than the target can move until MIN_VISIBLE pixels
+        // are left, smaller ones until they reach the edge.
+        let mut pan = -1000.0;
+        assert_eq!(pan_axis(-100, 300, 64, &mut pan), -284);
+        let mut pan = 1000.0;
+        assert_eq!(pan_axis(30, 4, 64, &mut pan), 60);
+    }
+
+    #[test]
+    fn pans_while_dragging() {
+        let mut view = View::default();
+        let scaling = Scaling::default();
+        assert!(!view.cursor_moved(PhysicalPosition::new(10.0, 10.0)));
+        view.set_dragging(true);
+        assert!(view.cursor_moved(PhysicalPosition::new(14.0, 7.0)));
+        let viewport = view
+            .layout(&scaling, SOURCE, PhysicalSize::new(100, 48))
+            .viewport;
+        assert_eq!((viewport.x, viewport.y), (22, -3));
+        view.reset();
+        let viewport = view
+            .layout(&scaling, SOURCE, PhysicalSize::new(100, 48))
+            .viewport;
+        assert_eq!((viewport.x, viewport.y), (18, 0));
+    }
+
+    #[test]
+    fn adds_up_touchpad_scrolling_into_steps() {
+        let mut view = View::default();
+        assert_eq!(view.scroll_steps(30.0), 0);
+        assert_eq!(view.scroll_steps(30.0), 1);
+        assert_eq!(view.scroll_steps(-70.0), -1);
+        assert_eq!(view.scroll_steps(0.0), 0);
+        assert_eq!(view.scroll_steps(60.0), 1);
+    }
+
+    #[test]
+    fn ignores_non_finite_touchpad_scrolling() {
+        let mut view = View::default();
+        assert_eq!(view.scroll_steps(30.0), 0);
+        assert_eq!(view.scroll_steps(f64::NAN), 0);
+        assert_eq!(view.scroll_steps(f64::INFINITY), 0);
+        assert_eq!(view.scroll_steps(30.0), 1);
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How often [`FileWatcher::poll`] looks at the files again.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Notices when files change on disk by polling their modification times,
+/// which is cheap for the handful of files a shader chain reads.
+pub struct FileWatcher {
+    /// Every file with its modification time when last polled, or `None` if it
+    /// couldn't be read.
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    last_poll: Instant,
+}
+
+impl FileWatcher {
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut watcher = Self {
+            files: Vec::new(),
+            last_poll: Instant::now(),
+        };
+        watcher.set_paths(paths);
+        watcher
+    }
+
+    /// Watches `paths` instead, as they are now.
+    pub fn set_paths(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        self.files.clear();
+        for path in paths {
+            if !self.files.iter().any(|(known, _)| *known == path) {
+                let modified = modified(&path);
+                self.files.push((path, modified));
+            }
+        }
+    }
+
+    /// Whether any file was modified, created or removed since the last poll.
+    /// Only looks at the files again every [`POLL_INTERVAL`].
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+        let mut changed = false;
+        for (path, last_modified) in &mut self.files {
+            let modified = modified(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    fn temporary_file(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("perfect-scale-watch-{}-{name}", std::process::id()));
+        fs::write(&path, "").unwrap();
+        path
+    }
+
+    /// Polls as if the interval had passed.
+    fn poll_now(watcher: &mut FileWatcher) -> bool {
+        watcher.last_poll = Instant::now() - POLL_INTERVAL;
+        watcher.poll()
+    }
+
+    fn touch(path: &Path, seconds: u64) {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    }
+
+    #[test]
+    fn notices_modified_files_once_per_interval() {
+        let path = temporary_file("modified");
+        touch(&path, 1_000_000);
+        let mut watcher = FileWatcher::new([path.clone(), path.clone()]);
+        assert!(!poll_now(&mut watcher));
+
+        touch(&path, 2_000_000);
+        assert!(!watcher.poll());
+        assert!(poll_now(&mut watcher));
+        assert!(!poll_now(&mut watcher));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn notices_removed_and_recreated_files() {
+        let path = temporary_file("recreated");
+        let mut watcher = FileWatcher::new([path.clone()]);
+
+        fs::remove_file(&path).unwrap();
+        assert!(poll_now(&mut watcher));
+        assert!(!poll_now(&mut watcher));
+
+        fs::write(&path, "").unwrap();
+        assert!(poll_now(&mut watcher));
+        assert!(!poll_now(&mut watcher));
+        fs::remove_file(&path).unwrap();
+    }
+}